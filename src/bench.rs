@@ -0,0 +1,115 @@
+use std::time::Instant;
+
+use crate::sort::{AlgorithmSpec, Counters, Trace};
+
+/// Summary of repeated timings, all in nanoseconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingStats {
+    pub min: u128,
+    pub median: u128,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub p90: u128,
+    pub p99: u128,
+    pub max: u128,
+}
+
+impl TimingStats {
+    /// Returns `None` for an empty sample set
+    pub fn from_samples(samples: &[u128]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let n = sorted.len();
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        } else {
+            sorted[n / 2]
+        };
+        let mean = sorted.iter().map(|&s| s as f64).sum::<f64>() / n as f64;
+        // Sample standard deviation; a single run has none
+        let std_dev = if n > 1 {
+            let var = sorted.iter().map(|&s| (s as f64 - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+            var.sqrt()
+        } else {
+            0.0
+        };
+
+        Some(Self {
+            min: sorted[0],
+            median,
+            mean,
+            std_dev,
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+            max: sorted[n - 1],
+        })
+    }
+}
+
+/// Nearest-rank percentile of already sorted samples
+pub fn percentile(sorted: &[u128], p: f64) -> u128 {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Timings and counters of one algorithm on one input
+pub struct BenchResult {
+    pub label: String,
+    pub size: usize,
+    pub stats: TimingStats,
+    pub counters: Counters,
+}
+
+/// Sorts copies of `input` with `spec`: `warmup` untimed runs, then `reps`
+/// timed ones. Every run starts from the same input.
+pub fn measure(spec: &AlgorithmSpec, input: &[i32], warmup: usize, reps: usize) -> BenchResult {
+    for _ in 0..warmup {
+        let mut data = input.to_vec();
+        Trace::count(&mut data, |rec| spec.run(rec));
+    }
+
+    let mut samples = Vec::with_capacity(reps);
+    let mut counters = Counters::default();
+    for _ in 0..reps.max(1) {
+        let mut data = input.to_vec();
+        let start_time = Instant::now();
+        counters = Trace::count(&mut data, |rec| spec.run(rec));
+        samples.push(start_time.elapsed().as_nanos());
+    }
+
+    BenchResult {
+        label: spec.label(),
+        size: input.len(),
+        stats: TimingStats::from_samples(&samples).expect("at least one timed run"),
+        counters,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_of_known_samples() {
+        let stats = TimingStats::from_samples(&[40, 10, 30, 20]).unwrap();
+        assert_eq!(stats.min, 10);
+        assert_eq!(stats.max, 40);
+        assert_eq!(stats.median, 25);
+        assert_eq!(stats.mean, 25.0);
+        assert!((stats.std_dev - 12.909944).abs() < 1e-6);
+        assert_eq!(stats.p90, 40);
+        assert!(TimingStats::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let sorted: Vec<u128> = (1..=100).collect();
+        assert_eq!(percentile(&sorted, 90.0), 90);
+        assert_eq!(percentile(&sorted, 99.0), 99);
+        assert_eq!(percentile(&[7], 50.0), 7);
+    }
+}
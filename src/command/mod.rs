@@ -1,3 +1,3 @@
-pub mod parser;
-
-pub use parser::CommandParser;
+pub mod parser;
+
+pub use parser::CommandParser;
@@ -1,173 +1,558 @@
-use std::str::FromStr;
-use thiserror::Error;
-
-/// Constants for command strings to avoid magic strings
-const CMD_RANDOMIZE: &str = ".randomize";
-const CMD_LIST: &str = ".list";
-const CMD_GRAPHS: &str = ".graphs";
-const CMD_SPEED: &str = ".speed";
-const CMD_GENERATE: &str = ".generate";
-const CMD_SORT: &str = ".sort";
-const CMD_COMPARE: &str = ".compare";
-const CMD_READLIST: &str = ".readlist";
-const CMD_QUICKSORT: &str = ".quicksort";
-const CMD_MERGESORT: &str = ".mergesort";
-const CMD_TIMSORT: &str = ".timsort";
-const CMD_RADIX: &str = ".radix";
-const CMD_HEAPSORT: &str = ".heapsort";
-
-/// Represents all possible commands in the application
-#[derive(Debug, Clone)]
-pub enum Command {
-    Randomize,
-    Generate {
-        count: Option<usize>,
-        range: (i32, i32),
-        no_repeats: bool,
-    },
-    List,
-    Graphs,
-    Speed(f64),
-    Sort(String),
-    Compare(String, String),
-    ReadList(String),
-    QuickSort,
-    MergeSort,
-    TimSort,
-    RadixSort,
-    HeapSort,
-}
-
-/// Possible errors that can occur during command parsing
-#[derive(Error, Debug)]
-pub enum ParseError {
-    #[error("Unknown command: {0}")]
-    UnknownCommand(String),
-    #[error("Invalid argument: {0}")]
-    InvalidArgument(String),
-    #[error("Missing argument for command: {0}")]
-    MissingArgument(String),
-    #[error("Invalid range: {0} must be less than {1}")]
-    InvalidRange(i32, i32),
-}
-
-pub struct CommandParser;
-
-impl CommandParser {
-    /// Parses a string input into a Command
-    ///
-    /// # Arguments
-    /// * `input` - The command string to parse
-    ///
-    /// # Returns
-    /// * `Result<Command, ParseError>` - The parsed command or an error
-    pub fn parse(input: &str) -> Result<Command, ParseError> {
-        let parts: Vec<&str> = input.trim().split_whitespace().collect();
-
-        if parts.is_empty() {
-            return Err(ParseError::UnknownCommand("Empty command".to_string()));
-        }
-
-        let cmd = parts[0];
-        let args = &parts[1..];
-
-        match cmd {
-            CMD_RANDOMIZE => Ok(Command::Randomize),
-            CMD_LIST => Ok(Command::List),
-            CMD_GRAPHS => Ok(Command::Graphs),
-            CMD_QUICKSORT => Ok(Command::QuickSort),
-            CMD_MERGESORT => Ok(Command::MergeSort),
-            CMD_TIMSORT => Ok(Command::TimSort),
-            CMD_RADIX => Ok(Command::RadixSort),
-            CMD_HEAPSORT => Ok(Command::HeapSort),
-            CMD_SPEED => Self::parse_speed(args),
-            CMD_GENERATE => Self::parse_generate(args),
-            CMD_SORT => Self::parse_sort(args),
-            CMD_COMPARE => Self::parse_compare(args),
-            CMD_READLIST => Self::parse_readlist(args),
-            _ => Err(ParseError::UnknownCommand(cmd.to_string())),
-        }
-    }
-
-    fn parse_speed(args: &[&str]) -> Result<Command, ParseError> {
-        let speed = args
-            .first()
-            .ok_or_else(|| ParseError::MissingArgument(CMD_SPEED.to_string()))?
-            .parse()
-            .map_err(|_| ParseError::InvalidArgument("Speed must be a valid number".to_string()))?;
-        Ok(Command::Speed(speed))
-    }
-
-    fn parse_generate(args: &[&str]) -> Result<Command, ParseError> {
-        let mut count: Option<usize> = None;
-        let mut range: (i32, i32) = (0, 10);
-        let mut no_repeats = false;
-
-        for arg in args {
-            match *arg {
-                "nr" => no_repeats = true,
-                arg if arg.contains('-') => {
-                    let (start, end) = Self::parse_range(arg)?;
-                    range = (start, end);
-                }
-                _ => {
-                    count = Some(arg.parse().map_err(|_| {
-                        ParseError::InvalidArgument("Count must be a positive number".to_string())
-                    })?);
-                }
-            }
-        }
-
-        if range.0 >= range.1 {
-            return Err(ParseError::InvalidRange(range.0, range.1));
-        }
-
-        Ok(Command::Generate {
-            count,
-            range,
-            no_repeats,
-        })
-    }
-
-    fn parse_range(range_str: &str) -> Result<(i32, i32), ParseError> {
-        let parts: Vec<&str> = range_str.split('-').collect();
-        if parts.len() != 2 {
-            return Err(ParseError::InvalidArgument(format!(
-                "Invalid range format: {}",
-                range_str
-            )));
-        }
-
-        let start = parts[0].parse().map_err(|_| {
-            ParseError::InvalidArgument(format!("Invalid range start: {}", parts[0]))
-        })?;
-        let end = parts[1].parse().map_err(|_| {
-            ParseError::InvalidArgument(format!("Invalid range end: {}", parts[1]))
-        })?;
-
-        Ok((start, end))
-    }
-
-    fn parse_sort(args: &[&str]) -> Result<Command, ParseError> {
-        let algorithm = args
-            .first()
-            .ok_or_else(|| ParseError::MissingArgument(CMD_SORT.to_string()))?;
-        Ok(Command::Sort(algorithm.to_string()))
-    }
-
-    fn parse_compare(args: &[&str]) -> Result<Command, ParseError> {
-        if args.len() < 2 {
-            return Err(ParseError::MissingArgument(
-                "Compare requires two algorithms".to_string(),
-            ));
-        }
-        Ok(Command::Compare(args[0].to_string(), args[1].to_string()))
-    }
-
-    fn parse_readlist(args: &[&str]) -> Result<Command, ParseError> {
-        let filename = args
-            .first()
-            .ok_or_else(|| ParseError::MissingArgument(CMD_READLIST.to_string()))?;
-        Ok(Command::ReadList(filename.to_string()))
-    }
-}
+use thiserror::Error;
+
+use crate::sort::{self, AlgorithmSpec};
+use crate::utils::Distribution;
+use crate::visualization::{self, Renderer};
+
+/// Constants for command strings to avoid magic strings
+const CMD_RANDOMIZE: &str = ".randomize";
+const CMD_SPEED: &str = ".speed";
+const CMD_GENERATE: &str = ".generate";
+const CMD_SORT: &str = ".sort";
+const CMD_COMPARE: &str = ".compare";
+const CMD_READLIST: &str = ".readlist";
+const CMD_BENCH: &str = ".bench";
+const CMD_SEED: &str = ".seed";
+const CMD_STABILITY: &str = ".stability";
+const CMD_PLAY: &str = ".play";
+const CMD_EXPORT: &str = ".export";
+const CMD_RECORD: &str = ".record";
+const CMD_ANTIQSORT: &str = ".antiqsort";
+
+/// Defaults for `.bench` when `reps=` / `warmup=` are not given
+const DEFAULT_BENCH_REPS: usize = 5;
+const DEFAULT_BENCH_WARMUP: usize = 1;
+
+/// Represents all possible commands in the application
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// `seed` overrides the next seed drawn from the session
+    Randomize {
+        seed: Option<u64>,
+    },
+    Generate {
+        count: Option<usize>,
+        range: (i32, i32),
+        no_repeats: bool,
+        distribution: Distribution,
+        seed: Option<u64>,
+    },
+    /// Sets the session seed, or shows it when no value is given
+    Seed(Option<u64>),
+    /// Turns stability checking on or off; toggles when no value is given
+    Stability(Option<bool>),
+    /// Sorts with the algorithm in the interactive player, or replays the
+    /// last recorded sort when no algorithm is given
+    Play(Option<AlgorithmSpec>),
+    /// Switches to the renderer registered under the command's name
+    View(&'static Renderer),
+    Speed(f64),
+    Sort(AlgorithmSpec),
+    /// Two or more algorithms on copies of the same array; `race` plays
+    /// them side by side instead of only printing the table
+    Compare {
+        algorithms: Vec<AlgorithmSpec>,
+        race: bool,
+    },
+    ReadList(String),
+    /// Repeated timed runs; empty `sizes` means the current array
+    Bench {
+        algorithms: Vec<AlgorithmSpec>,
+        sizes: Vec<usize>,
+        reps: usize,
+        warmup: usize,
+        seed: Option<u64>,
+    },
+    /// Writes the last recorded sort to a file
+    Export {
+        format: ExportFormat,
+        path: String,
+    },
+    /// Starts recording frames to an asciinema file, or stops with `None`
+    Record(Option<String>),
+    /// Builds a worst-case input for the algorithm; `None` keeps the
+    /// current array length
+    Antiqsort {
+        algorithm: AlgorithmSpec,
+        count: Option<usize>,
+    },
+}
+
+/// File formats for `.export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Standalone SVG animated with SMIL
+    Svg,
+    /// Looping GIF; the size and frame rate fall back to defaults when not given
+    Gif {
+        size: Option<(u16, u16)>,
+        fps: Option<u16>,
+    },
+}
+
+/// Possible errors that can occur during command parsing
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("Missing argument for command: {0}")]
+    MissingArgument(String),
+    #[error("Unknown sorting algorithm: {0}")]
+    UnknownAlgorithm(String),
+    #[error("Invalid range: {0} must be less than {1}")]
+    InvalidRange(i32, i32),
+}
+
+impl Command {
+    /// Seeds the random choices of every algorithm in the command, so that
+    /// e.g. `pivot=random` follows the session seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        match &mut self {
+            Command::Sort(spec) | Command::Play(Some(spec)) | Command::Antiqsort { algorithm: spec, .. } => {
+                spec.set_seed(seed)
+            }
+            Command::Compare { algorithms, .. } | Command::Bench { algorithms, .. } => {
+                algorithms.iter_mut().for_each(|spec| spec.set_seed(seed))
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
+pub struct CommandParser;
+
+impl CommandParser {
+    /// Parses a string input into a Command
+    ///
+    /// # Arguments
+    /// * `input` - The command string to parse
+    ///
+    /// # Returns
+    /// * `Result<Command, ParseError>` - The parsed command or an error
+    pub fn parse(input: &str) -> Result<Command, ParseError> {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+
+        if parts.is_empty() {
+            return Err(ParseError::UnknownCommand("Empty command".to_string()));
+        }
+
+        let cmd = parts[0];
+        let args = &parts[1..];
+
+        match cmd {
+            CMD_RANDOMIZE => Self::parse_randomize(args),
+            CMD_SEED => Self::parse_seed(args),
+            CMD_STABILITY => Self::parse_stability(args),
+            CMD_PLAY => Self::parse_play(args),
+            CMD_SPEED => Self::parse_speed(args),
+            CMD_GENERATE => Self::parse_generate(args),
+            CMD_SORT => Self::parse_sort(args),
+            CMD_COMPARE => Self::parse_compare(args),
+            CMD_READLIST => Self::parse_readlist(args),
+            CMD_BENCH => Self::parse_bench(args),
+            CMD_EXPORT => Self::parse_export(args),
+            CMD_RECORD => Self::parse_record(args),
+            CMD_ANTIQSORT => Self::parse_antiqsort(args),
+            // `.list`, `.graphs` and so on switch renderers, `.quicksort`,
+            // `.heapsort` and so on are shortcuts for `.sort <name>`
+            _ => {
+                let name = cmd.strip_prefix('.').ok_or_else(|| ParseError::UnknownCommand(cmd.to_string()))?;
+                if let Some(renderer) = visualization::find(name) {
+                    Ok(Command::View(renderer))
+                } else if let Some(algorithm) = sort::find(name) {
+                    Ok(Command::Sort(Self::parse_options(algorithm.name, args)?))
+                } else {
+                    Err(ParseError::UnknownCommand(cmd.to_string()))
+                }
+            }
+        }
+    }
+
+    fn parse_speed(args: &[&str]) -> Result<Command, ParseError> {
+        let speed = args
+            .first()
+            .ok_or_else(|| ParseError::MissingArgument(CMD_SPEED.to_string()))?
+            .parse()
+            .map_err(|_| ParseError::InvalidArgument("Speed must be a valid number".to_string()))?;
+        Ok(Command::Speed(speed))
+    }
+
+    fn parse_randomize(args: &[&str]) -> Result<Command, ParseError> {
+        let mut seed = None;
+        for arg in args {
+            match arg.strip_prefix("seed=") {
+                Some(value) => seed = Some(Self::parse_seed_value(value)?),
+                None => return Err(ParseError::InvalidArgument(format!("Unexpected argument: {}", arg))),
+            }
+        }
+        Ok(Command::Randomize { seed })
+    }
+
+    fn parse_seed(args: &[&str]) -> Result<Command, ParseError> {
+        let seed = args.first().map(|value| Self::parse_seed_value(value)).transpose()?;
+        Ok(Command::Seed(seed))
+    }
+
+    fn parse_play(args: &[&str]) -> Result<Command, ParseError> {
+        match args.first() {
+            Some(algorithm) => Ok(Command::Play(Some(Self::parse_options(algorithm, &args[1..])?))),
+            None => Ok(Command::Play(None)),
+        }
+    }
+
+    fn parse_stability(args: &[&str]) -> Result<Command, ParseError> {
+        let enabled = match args.first().copied() {
+            None => None,
+            Some("on") => Some(true),
+            Some("off") => Some(false),
+            Some(arg) => {
+                return Err(ParseError::InvalidArgument(format!(
+                    "Expected on or off, got: {}",
+                    arg
+                )));
+            }
+        };
+        Ok(Command::Stability(enabled))
+    }
+
+    fn parse_seed_value(value: &str) -> Result<u64, ParseError> {
+        value.parse().map_err(|_| {
+            ParseError::InvalidArgument(format!("Seed must be a non-negative integer: {}", value))
+        })
+    }
+
+    fn parse_generate(args: &[&str]) -> Result<Command, ParseError> {
+        let mut count: Option<usize> = None;
+        let mut range: (i32, i32) = (0, 10);
+        let mut no_repeats = false;
+        let mut shape = None;
+        let mut k = None;
+        let mut seed = None;
+
+        for arg in args {
+            match *arg {
+                "nr" => no_repeats = true,
+                arg if arg.starts_with("seed=") => {
+                    seed = Some(Self::parse_seed_value(&arg["seed=".len()..])?)
+                }
+                arg if arg.starts_with("shape=") => shape = Some(&arg["shape=".len()..]),
+                arg if arg.starts_with("k=") => {
+                    k = Some(arg["k=".len()..].parse().map_err(|_| {
+                        ParseError::InvalidArgument(format!("Invalid shape parameter: {}", arg))
+                    })?);
+                }
+                arg if arg.contains('-') => {
+                    let (start, end) = Self::parse_range(arg)?;
+                    range = (start, end);
+                }
+                _ => {
+                    count = Some(arg.parse().map_err(|_| {
+                        ParseError::InvalidArgument("Count must be a positive number".to_string())
+                    })?);
+                }
+            }
+        }
+
+        if range.0 >= range.1 {
+            return Err(ParseError::InvalidRange(range.0, range.1));
+        }
+
+        let distribution = match shape {
+            Some(name) => Distribution::from_name(name, k).ok_or_else(|| {
+                ParseError::InvalidArgument(format!(
+                    "Unknown shape: {} (expected one of: {})",
+                    name,
+                    Distribution::NAMES.join(", ")
+                ))
+            })?,
+            None => Distribution::default(),
+        };
+
+        Ok(Command::Generate {
+            count,
+            range,
+            no_repeats,
+            distribution,
+            seed,
+        })
+    }
+
+    /// Parses `min-max`; either bound may be negative, e.g. `-20-20` or `-50--10`
+    fn parse_range(range_str: &str) -> Result<(i32, i32), ParseError> {
+        // The separator is the first '-' that is not the sign of the start
+        let separator = range_str
+            .get(1..)
+            .and_then(|rest| rest.find('-'))
+            .map(|i| i + 1)
+            .ok_or_else(|| {
+                ParseError::InvalidArgument(format!("Invalid range format: {}", range_str))
+            })?;
+        let (start_str, end_str) = (&range_str[..separator], &range_str[separator + 1..]);
+
+        let start = start_str.parse().map_err(|_| {
+            ParseError::InvalidArgument(format!("Invalid range start: {}", start_str))
+        })?;
+        let end = end_str.parse().map_err(|_| {
+            ParseError::InvalidArgument(format!("Invalid range end: {}", end_str))
+        })?;
+
+        Ok((start, end))
+    }
+
+    fn parse_sort(args: &[&str]) -> Result<Command, ParseError> {
+        let algorithm = args
+            .first()
+            .ok_or_else(|| ParseError::MissingArgument(CMD_SORT.to_string()))?;
+        Ok(Command::Sort(Self::parse_options(algorithm, &args[1..])?))
+    }
+
+    /// Resolves a name or alias from the registry and applies options given
+    /// as separate words, e.g. `radix msd base=16`
+    fn parse_options(name: &str, options: &[&str]) -> Result<AlgorithmSpec, ParseError> {
+        let algorithm =
+            sort::find(name).ok_or_else(|| ParseError::UnknownAlgorithm(name.to_string()))?;
+        let mut spec = AlgorithmSpec::new(algorithm);
+        for option in options {
+            spec.set_option(option).map_err(ParseError::InvalidArgument)?;
+        }
+        Ok(spec)
+    }
+
+    /// Parses an algorithm written as a single word, with options after
+    /// a colon: `radix:msd,base=16`
+    fn parse_algorithm(word: &str) -> Result<AlgorithmSpec, ParseError> {
+        match word.split_once(':') {
+            Some((name, options)) => {
+                let options: Vec<&str> = options.split(',').filter(|o| !o.is_empty()).collect();
+                Self::parse_options(name, &options)
+            }
+            None => Self::parse_options(word, &[]),
+        }
+    }
+
+    /// `.antiqsort quick pivot=median3 [count]`: the algorithm with its
+    /// options, optionally followed by the array length
+    fn parse_antiqsort(args: &[&str]) -> Result<Command, ParseError> {
+        let (name, rest) = args
+            .split_first()
+            .ok_or_else(|| ParseError::MissingArgument(CMD_ANTIQSORT.to_string()))?;
+        let (count, options) = match rest.split_last() {
+            Some((last, options)) if last.chars().all(|c| c.is_ascii_digit()) => {
+                let count = last.parse().map_err(|_| {
+                    ParseError::InvalidArgument(format!("Count is too large: {}", last))
+                })?;
+                (Some(count), options)
+            }
+            _ => (None, rest),
+        };
+        let algorithm = Self::parse_options(name, options)?;
+        Ok(Command::Antiqsort { algorithm, count })
+    }
+
+    /// `.compare quick merge heap [race]`
+    fn parse_compare(args: &[&str]) -> Result<Command, ParseError> {
+        let race = args.contains(&"race");
+        let algorithms = args
+            .iter()
+            .filter(|arg| **arg != "race")
+            .map(|arg| Self::parse_algorithm(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        if algorithms.len() < 2 {
+            return Err(ParseError::MissingArgument(
+                "Compare requires at least two algorithms".to_string(),
+            ));
+        }
+        Ok(Command::Compare { algorithms, race })
+    }
+
+    /// `.bench quick radix:msd sizes=100,1000 reps=10 warmup=2 seed=7`: every
+    /// word that is not a `sizes=`, `reps=`, `warmup=` or `seed=` setting is
+    /// an algorithm
+    fn parse_bench(args: &[&str]) -> Result<Command, ParseError> {
+        let mut algorithms = Vec::new();
+        let mut sizes = Vec::new();
+        let mut reps = DEFAULT_BENCH_REPS;
+        let mut warmup = DEFAULT_BENCH_WARMUP;
+        let mut seed = None;
+
+        for arg in args {
+            match arg.split_once('=') {
+                Some(("sizes" | "n", value)) => {
+                    for size in value.split(',').filter(|s| !s.is_empty()) {
+                        sizes.push(Self::parse_count(size, "Size")?);
+                    }
+                }
+                Some(("seed", value)) => seed = Some(Self::parse_seed_value(value)?),
+                Some(("reps", value)) => reps = Self::parse_count(value, "Repetition count")?,
+                Some(("warmup", value)) => {
+                    warmup = value.parse().map_err(|_| {
+                        ParseError::InvalidArgument(format!("Invalid warmup count: {}", value))
+                    })?
+                }
+                _ => algorithms.push(Self::parse_algorithm(arg)?),
+            }
+        }
+
+        if algorithms.is_empty() {
+            return Err(ParseError::MissingArgument(
+                "Bench requires at least one algorithm".to_string(),
+            ));
+        }
+
+        Ok(Command::Bench {
+            algorithms,
+            sizes,
+            reps,
+            warmup,
+            seed,
+        })
+    }
+
+    /// Parses a strictly positive number such as an array size
+    fn parse_count(value: &str, what: &str) -> Result<usize, ParseError> {
+        match value.parse() {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(ParseError::InvalidArgument(format!(
+                "{} must be a positive number: {}",
+                what, value
+            ))),
+        }
+    }
+
+    /// Parses `.export <format> <file>`; `gif` also takes `[width height fps]`
+    fn parse_export(args: &[&str]) -> Result<Command, ParseError> {
+        let (format, path, rest) = match args {
+            [format, path, rest @ ..] => (format, path, rest),
+            _ => return Err(ParseError::MissingArgument(CMD_EXPORT.to_string())),
+        };
+        let format = match (format.to_lowercase().as_str(), rest) {
+            ("svg", []) => ExportFormat::Svg,
+            ("gif", []) => ExportFormat::Gif { size: None, fps: None },
+            ("gif", [width, height]) => ExportFormat::Gif {
+                size: Some((Self::parse_dimension(width, "width")?, Self::parse_dimension(height, "height")?)),
+                fps: None,
+            },
+            ("gif", [width, height, fps]) => ExportFormat::Gif {
+                size: Some((Self::parse_dimension(width, "width")?, Self::parse_dimension(height, "height")?)),
+                fps: Some(Self::parse_dimension(fps, "fps")?),
+            },
+            ("svg" | "gif", _) => {
+                return Err(ParseError::InvalidArgument(format!(
+                    "unexpected arguments: {}",
+                    rest.join(" ")
+                )));
+            }
+            (other, _) => {
+                return Err(ParseError::InvalidArgument(format!(
+                    "unknown export format: {} (expected svg or gif)",
+                    other
+                )));
+            }
+        };
+        Ok(Command::Export { format, path: path.to_string() })
+    }
+
+    /// Parses `.record <file>` and `.record stop`
+    fn parse_record(args: &[&str]) -> Result<Command, ParseError> {
+        match args {
+            [] => Err(ParseError::MissingArgument(CMD_RECORD.to_string())),
+            ["stop" | "off"] => Ok(Command::Record(None)),
+            [path] => Ok(Command::Record(Some(path.to_string()))),
+            _ => Err(ParseError::InvalidArgument(format!(
+                "unexpected arguments: {}",
+                args[1..].join(" ")
+            ))),
+        }
+    }
+
+    /// Parses a positive 16-bit number such as an image side
+    fn parse_dimension(value: &str, what: &str) -> Result<u16, ParseError> {
+        match value.parse() {
+            Ok(number) if number > 0 => Ok(number),
+            _ => Err(ParseError::InvalidArgument(format!(
+                "{} must be a number from 1 to 65535: {}",
+                what, value
+            ))),
+        }
+    }
+
+    fn parse_readlist(args: &[&str]) -> Result<Command, ParseError> {
+        let filename = args
+            .first()
+            .ok_or_else(|| ParseError::MissingArgument(CMD_READLIST.to_string()))?;
+        Ok(Command::ReadList(filename.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range_with_negative_bounds() {
+        assert_eq!(CommandParser::parse_range("0-500").unwrap(), (0, 500));
+        assert_eq!(CommandParser::parse_range("-20-20").unwrap(), (-20, 20));
+        assert_eq!(CommandParser::parse_range("-50--10").unwrap(), (-50, -10));
+        assert!(CommandParser::parse_range("-20").is_err());
+        assert!(CommandParser::parse_range("1-2-3").is_err());
+    }
+
+    #[test]
+    fn test_parse_generate_shape() {
+        match CommandParser::parse(".generate 100 0-500 shape=organpipe").unwrap() {
+            Command::Generate { count, range, distribution, .. } => {
+                assert_eq!(count, Some(100));
+                assert_eq!(range, (0, 500));
+                assert_eq!(distribution, Distribution::OrganPipe);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        match CommandParser::parse(".generate 50 shape=nearly k=3").unwrap() {
+            Command::Generate { distribution, .. } => {
+                assert_eq!(distribution, Distribution::NearlySorted(Some(3)))
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(CommandParser::parse(".generate shape=zigzag").is_err());
+    }
+
+    #[test]
+    fn test_parse_export() {
+        match CommandParser::parse(".export gif run.gif 320 160 25").unwrap() {
+            Command::Export { format, path } => {
+                assert_eq!(format, ExportFormat::Gif { size: Some((320, 160)), fps: Some(25) });
+                assert_eq!(path, "run.gif");
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(CommandParser::parse(".export svg run.svg 320").is_err());
+        assert!(CommandParser::parse(".export gif run.gif 0 100").is_err());
+    }
+
+    #[test]
+    fn test_parse_antiqsort() {
+        match CommandParser::parse(".antiqsort quick hoare pivot=median3 300").unwrap() {
+            Command::Antiqsort { algorithm, count } => {
+                assert_eq!(algorithm.label(), "quicksort partition=hoare pivot=median3");
+                assert_eq!(count, Some(300));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        assert!(matches!(
+            CommandParser::parse(".antiqsort heap").unwrap(),
+            Command::Antiqsort { count: None, .. }
+        ));
+        assert!(CommandParser::parse(".antiqsort").is_err());
+        assert!(CommandParser::parse(".antiqsort quick pivot=best").is_err());
+    }
+
+    #[test]
+    fn test_parse_view() {
+        assert!(matches!(CommandParser::parse(".graphs").unwrap(), Command::View(r) if r.name == "graphs"));
+        assert!(matches!(CommandParser::parse(".Network").unwrap(), Command::View(r) if r.name == "network"));
+        assert!(CommandParser::parse("list").is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use super::{Frame, vertical_scale};
+use crate::visualization::Highlight;
+
+pub const DEFAULT_WIDTH: u16 = 640;
+pub const DEFAULT_HEIGHT: u16 = 320;
+/// Задержка в GIF задаётся в сотых долях секунды, а меньше 2 браузеры не показывают
+pub const MAX_FPS: u16 = 50;
+/// Сколько держится итоговый кадр, в сотых долях секунды
+const FINAL_HOLD_CS: u16 = 200;
+
+/// Палитра: фон и цвета ролей в порядке `ROLES`, индекс роли — позиция + 1
+const BACKGROUND: [u8; 3] = [30, 30, 30];
+const ROLES: [Highlight; 7] = [
+    Highlight::None,
+    Highlight::Compare,
+    Highlight::Swap,
+    Highlight::Pivot,
+    Highlight::Sorted,
+    Highlight::Aux,
+    Highlight::AuxWrite,
+];
+/// Бит на пиксель: 8 цветов палитры
+const COLOR_BITS: u8 = 3;
+const MAX_CODE_BITS: u8 = 12;
+
+/// Кодирует кадры в зацикленный GIF89a: каждый кадр растрируется в
+/// столбчатую диаграмму с палитрой цветов подсветки и сжимается LZW
+pub fn render(frames: &[Frame], width: u16, height: u16, fps: u16) -> Vec<u8> {
+    let mut gif = Vec::new();
+    gif.extend_from_slice(b"GIF89a");
+    gif.extend_from_slice(&width.to_le_bytes());
+    gif.extend_from_slice(&height.to_le_bytes());
+    // Глобальная палитра из 2^COLOR_BITS цветов, фон — цвет 0
+    gif.extend_from_slice(&[0xF0 | (COLOR_BITS - 1), 0, 0]);
+    gif.extend_from_slice(&BACKGROUND);
+    for role in ROLES {
+        gif.extend_from_slice(&role.rgb());
+    }
+    // Расширение NETSCAPE2.0: бесконечный повтор
+    gif.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    let delay = (100 / fps.clamp(1, MAX_FPS)).max(2);
+    let (zero, unit) = vertical_scale(frames, height as u32);
+    for (index, frame) in frames.iter().enumerate() {
+        let delay = if index + 1 == frames.len() { FINAL_HOLD_CS } else { delay };
+        // Управление графикой: задержка кадра, без прозрачности
+        gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+        gif.extend_from_slice(&delay.to_le_bytes());
+        gif.extend_from_slice(&[0x00, 0x00]);
+        // Кадр на весь экран без локальной палитры
+        gif.push(0x2C);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&width.to_le_bytes());
+        gif.extend_from_slice(&height.to_le_bytes());
+        gif.push(0x00);
+
+        let pixels = rasterize(frame, width as usize, height as usize, zero, unit);
+        gif.push(COLOR_BITS);
+        for block in lzw_encode(&pixels, COLOR_BITS).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0x00);
+    }
+    gif.push(0x3B);
+    gif
+}
+
+/// Индексы палитры для каждого пикселя, построчно сверху вниз
+fn rasterize(frame: &Frame, width: usize, height: usize, zero: f64, unit: f64) -> Vec<u8> {
+    let mut pixels = vec![0u8; width * height];
+    let count = frame.data.len();
+    for (i, &value) in frame.data.iter().enumerate() {
+        let left = i * width / count;
+        let mut right = ((i + 1) * width / count).max(left + 1).min(width);
+        // Промежуток между столбцами, если они достаточно широкие
+        if right - left >= 3 {
+            right -= 1;
+        }
+        let (top, bottom) = if value >= 0 {
+            (zero - value as f64 * unit, zero)
+        } else {
+            (zero, zero - value as f64 * unit)
+        };
+        let (top, bottom) = (top.round() as usize, (bottom.round() as usize).min(height));
+        // Ненулевое значение видно хотя бы одной строкой пикселей
+        let bottom = if value != 0 && bottom == top { (top + 1).min(height) } else { bottom };
+
+        let mark = frame.marks.get(i).copied().unwrap_or_default();
+        let color = ROLES.iter().position(|&role| role == mark).unwrap_or(0) as u8 + 1;
+        for y in top..bottom {
+            pixels[y * width + left..y * width + right].fill(color);
+        }
+    }
+    pixels
+}
+
+/// Упаковывает коды переменной длины, младшими битами вперёд
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Сжатие LZW в варианте GIF: коды растут от `min_bits + 1` до 12 бит,
+/// а при заполнении таблицы она сбрасывается кодом очистки
+fn lzw_encode(pixels: &[u8], min_bits: u8) -> Vec<u8> {
+    let clear: u16 = 1 << min_bits;
+    let end = clear + 1;
+    let mut out = BitWriter { bytes: Vec::new(), buffer: 0, bits: 0 };
+    // Таблица строк: код строки-префикса и следующий пиксель дают код
+    // продолжения, 0 — такой строки ещё нет
+    let alphabet = clear as usize;
+    let mut table = vec![0u16; alphabet << MAX_CODE_BITS];
+    let mut next_code = end + 1;
+    let mut width = min_bits + 1;
+
+    out.write(clear, width);
+    let mut pixels = pixels.iter();
+    let Some(&first) = pixels.next() else {
+        out.write(end, width);
+        return out.finish();
+    };
+    let mut prefix = first as u16;
+    for &pixel in pixels {
+        let slot = prefix as usize * alphabet + pixel as usize;
+        if table[slot] != 0 {
+            prefix = table[slot];
+            continue;
+        }
+        out.write(prefix, width);
+        if next_code == 1 << MAX_CODE_BITS {
+            out.write(clear, width);
+            table.fill(0);
+            next_code = end + 1;
+            width = min_bits + 1;
+        } else {
+            table[slot] = next_code;
+            next_code += 1;
+            // Декодер добавляет коды на шаг позже, поэтому ширина растёт,
+            // когда следующий код уже не помещается
+            if next_code > 1 << width && width < MAX_CODE_BITS {
+                width += 1;
+            }
+        }
+        prefix = pixel as u16;
+    }
+    out.write(prefix, width);
+    out.write(end, width);
+    out.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Декодер LZW в варианте GIF, только для проверки кодировщика
+    fn lzw_decode(bytes: &[u8], min_bits: u8) -> Vec<u8> {
+        let clear = 1u16 << min_bits;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut width = min_bits + 1;
+        let (mut buffer, mut bits, mut bytes) = (0u32, 0u8, bytes.iter());
+        let mut previous: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        loop {
+            while bits < width {
+                buffer |= (*bytes.next().unwrap() as u32) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << width) - 1)) as u16;
+            buffer >>= width;
+            bits -= width;
+
+            if code == clear {
+                table = (0..clear).map(|c| vec![c as u8]).collect();
+                table.extend([Vec::new(), Vec::new()]);
+                width = min_bits + 1;
+                previous = None;
+                continue;
+            }
+            if code == clear + 1 {
+                return out;
+            }
+            let entry = match (table.get(code as usize), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(prev)) => [prev.clone(), vec![prev[0]]].concat(),
+                (None, None) => panic!("unknown first code {}", code),
+            };
+            out.extend_from_slice(&entry);
+            if let Some(prev) = previous {
+                table.push([prev, vec![entry[0]]].concat());
+                if table.len() == 1 << width && width < MAX_CODE_BITS {
+                    width += 1;
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        // Длинные повторы заполняют таблицу и проверяют сброс на 4096 кодах
+        let pixels: Vec<u8> = (0..200_000u64).map(|i| ((i * i / 7 + i / 300) % 8) as u8).collect();
+        assert_eq!(lzw_decode(&lzw_encode(&pixels, COLOR_BITS), COLOR_BITS), pixels);
+        assert_eq!(lzw_decode(&lzw_encode(&[5], COLOR_BITS), COLOR_BITS), vec![5]);
+    }
+
+    #[test]
+    fn test_bars_use_palette_of_their_role() {
+        let frame = Frame { data: vec![2, -1], marks: vec![Highlight::Compare, Highlight::Sorted] };
+        let (zero, unit) = vertical_scale(std::slice::from_ref(&frame), 3);
+        let pixels = rasterize(&frame, 2, 3, zero, unit);
+        assert_eq!(pixels, vec![2, 0, 2, 0, 0, 5]);
+    }
+}
@@ -0,0 +1,153 @@
+pub mod gif;
+pub mod svg;
+
+use std::cell::{Cell, RefCell};
+
+use crate::sort::{SortEvent, Trace};
+use crate::visualization::{Highlight, RenderStats, Visualization};
+
+/// Больше кадров в файл не попадает: длинные сортировки прореживаются
+pub const MAX_FRAMES: usize = 600;
+
+/// Кадр анимации: массив и роли элементов, те же, что получает `render_step`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub data: Vec<i32>,
+    pub marks: Vec<Highlight>,
+}
+
+/// Визуализация, которая ничего не рисует, а складывает в список кадры
+/// с номерами из `keep`; остальные пропускаются без копирования
+#[derive(Default)]
+struct FrameCollector {
+    data: Vec<i32>,
+    marks: Vec<Highlight>,
+    /// Номера сохраняемых кадров по возрастанию
+    keep: Vec<usize>,
+    /// Сколько кадров уже нарисовано
+    rendered: Cell<usize>,
+    frames: RefCell<Vec<Frame>>,
+    stats: RenderStats,
+}
+
+impl FrameCollector {
+    fn push(&self, frame: impl FnOnce() -> Frame) {
+        let index = self.rendered.get();
+        self.rendered.set(index + 1);
+        if self.keep.binary_search(&index).is_ok() {
+            self.frames.borrow_mut().push(frame());
+        }
+    }
+}
+
+impl Visualization for FrameCollector {
+    fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
+    fn stats_mut(&mut self) -> &mut RenderStats {
+        &mut self.stats
+    }
+
+    fn set_frame(&mut self, data: Vec<i32>, marks: Vec<Highlight>) {
+        self.data = data;
+        self.marks = marks;
+    }
+
+    fn set_aux(&mut self, _aux: Vec<i32>, _active: Option<usize>) {}
+
+    fn frame(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn final_frame(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn render(&self) {
+        self.push(|| Frame {
+            data: self.data.clone(),
+            marks: self.marks.clone(),
+        });
+    }
+}
+
+/// Кадры проигрывания трассы: начальный массив, по кадру на каждый шаг,
+/// как при `.sort`, и итоговый массив, целиком отмеченный готовым.
+/// Если кадров больше `max_frames`, берутся равномерно распределённые;
+/// в памяти хранятся только они
+pub fn collect_frames(trace: &Trace, max_frames: usize) -> Vec<Frame> {
+    // Кадр рисуется на каждое событие, кроме начала фазы и отметки готовых
+    // позиций (см. `Visualization::on_event`), плюс начальный и итоговый
+    let steps = trace
+        .events
+        .iter()
+        .filter(|event| !matches!(event, SortEvent::PhaseStart(_) | SortEvent::MarkSorted(_)))
+        .count();
+    let mut collector = FrameCollector {
+        data: trace.initial.clone(),
+        keep: sample(steps + 2, max_frames),
+        ..FrameCollector::default()
+    };
+    collector.render();
+    collector.play(trace);
+
+    let mut data = trace.initial.clone();
+    let mut aux = Vec::new();
+    for event in &trace.events {
+        event.apply(&mut data, &mut aux);
+    }
+    collector.push(|| Frame {
+        marks: vec![Highlight::Sorted; data.len()],
+        data,
+    });
+    collector.frames.into_inner()
+}
+
+/// Номера не больше чем `max` из `total` кадров, включая первый и последний
+fn sample(total: usize, max: usize) -> Vec<usize> {
+    if total <= max || max < 2 {
+        return (0..total).collect();
+    }
+    let last = total - 1;
+    (0..max).map(|k| k * last / (max - 1)).collect()
+}
+
+/// Вертикальный масштаб для всех кадров: положение нулевой линии от верха
+/// и пикселей на единицу значения
+fn vertical_scale(frames: &[Frame], height: u32) -> (f64, f64) {
+    let values = frames.iter().flat_map(|frame| frame.data.iter().copied());
+    let (low, high) = values.fold((0i64, 0i64), |(low, high), v| (low.min(v as i64), high.max(v as i64)));
+    let unit = height as f64 / (high - low).max(1) as f64;
+    (high as f64 * unit, unit)
+}
+
+/// Длительность кадра при текущей скорости, как задержка при проигрывании
+pub fn frame_millis(speed: f64) -> u64 {
+    if speed > 0.0 { ((100.0 / speed) as u64).max(10) } else { 100 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::bubblesort;
+
+    #[test]
+    fn test_frames_follow_the_replay() {
+        let mut data = vec![3, 1, 2];
+        let trace = Trace::record(&mut data, bubblesort);
+        let frames = collect_frames(&trace, MAX_FRAMES);
+
+        assert_eq!(frames[0].data, vec![3, 1, 2]);
+        assert_eq!(frames[1].marks[..2], [Highlight::Compare, Highlight::Compare]);
+        let last = frames.last().unwrap();
+        assert_eq!(last.data, vec![1, 2, 3]);
+        assert!(last.marks.iter().all(|&mark| mark == Highlight::Sorted));
+
+        let sampled = collect_frames(&trace, 3);
+        assert_eq!(sampled.len(), 3);
+        assert_eq!(sampled[0], frames[0]);
+        assert_eq!(sampled[1], frames[(frames.len() - 1) / 2]);
+        assert_eq!(sampled.last(), frames.last());
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt::Write;
+
+use super::{Frame, vertical_scale};
+
+pub const DEFAULT_WIDTH: u32 = 800;
+pub const DEFAULT_HEIGHT: u32 = 400;
+/// Сколько держится итоговый кадр перед повтором анимации
+const FINAL_HOLD_MS: u64 = 2000;
+const BACKGROUND: &str = "#1e1e1e";
+
+/// Собирает самостоятельный SVG, в котором столбцы меняют высоту и цвет
+/// по кадрам с помощью SMIL-анимации (`<animate calcMode="discrete">`).
+///
+/// Для каждого столбца записываются только кадры, где он изменился, поэтому
+/// файл растёт с числом операций, а не с произведением кадров на элементы.
+pub fn render(frames: &[Frame], frame_ms: u64, width: u32, height: u32) -> String {
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, BACKGROUND);
+
+    let Some(first) = frames.first() else {
+        svg.push_str("</svg>\n");
+        return svg;
+    };
+    let total_ms = frames.len() as u64 * frame_ms + FINAL_HOLD_MS;
+    let key_time = |frame: usize| format!("{:.5}", (frame as u64 * frame_ms) as f64 / total_ms as f64);
+
+    // Нулевая линия делит высоту между положительными и отрицательными значениями
+    let (zero, unit) = vertical_scale(frames, height);
+    let bar_width = width as f64 / first.data.len().max(1) as f64;
+
+    for i in 0..first.data.len() {
+        let (mut ys, mut heights, mut fills) = (Vec::new(), Vec::new(), Vec::new());
+        for (index, frame) in frames.iter().enumerate() {
+            let value = frame.data.get(i).copied().unwrap_or(0) as f64;
+            let y = if value >= 0.0 { zero - value * unit } else { zero };
+            push_change(&mut ys, index, format!("{:.2}", y));
+            push_change(&mut heights, index, format!("{:.2}", value.abs() * unit));
+            let [r, g, b] = frame.marks.get(i).copied().unwrap_or_default().rgb();
+            push_change(&mut fills, index, format!("#{:02x}{:02x}{:02x}", r, g, b));
+        }
+
+        let _ = write!(
+            svg,
+            r#"<rect x="{:.2}" y="{}" width="{:.2}" height="{}" fill="{}">"#,
+            i as f64 * bar_width,
+            ys[0].1,
+            (bar_width - 1.0).max(bar_width * 0.8),
+            heights[0].1,
+            fills[0].1
+        );
+        for (attribute, changes) in [("y", &ys), ("height", &heights), ("fill", &fills)] {
+            animate(&mut svg, attribute, changes, total_ms, &key_time);
+        }
+        svg.push_str("</rect>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Добавляет значение, только если оно отличается от предыдущего
+fn push_change<V: PartialEq>(changes: &mut Vec<(usize, V)>, frame: usize, value: V) {
+    if changes.last().is_none_or(|(_, last)| *last != value) {
+        changes.push((frame, value));
+    }
+}
+
+fn animate(
+    svg: &mut String,
+    attribute: &str,
+    changes: &[(usize, String)],
+    total_ms: u64,
+    key_time: &dyn Fn(usize) -> String,
+) {
+    // Значение, которое не меняется, уже записано в сам прямоугольник
+    if changes.len() < 2 {
+        return;
+    }
+    let values: Vec<&str> = changes.iter().map(|(_, value)| value.as_str()).collect();
+    let times: Vec<String> = changes.iter().map(|(frame, _)| key_time(*frame)).collect();
+    let _ = write!(
+        svg,
+        r#"<animate attributeName="{}" dur="{}ms" repeatCount="indefinite" calcMode="discrete" values="{}" keyTimes="{}"/>"#,
+        attribute,
+        total_ms,
+        values.join(";"),
+        times.join(";")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visualization::Highlight;
+
+    #[test]
+    fn test_animates_only_changes() {
+        let frames = vec![
+            Frame { data: vec![1, 2], marks: vec![Highlight::None; 2] },
+            Frame { data: vec![1, 2], marks: vec![Highlight::Compare; 2] },
+            Frame { data: vec![1, -2], marks: vec![Highlight::Sorted; 2] },
+        ];
+        let svg = render(&frames, 100, 200, 100);
+
+        assert_eq!(svg.matches("<rect x=").count(), 2);
+        // У первого столбца меняется только цвет, у второго ещё и высота
+        assert_eq!(svg.matches(r#"attributeName="fill""#).count(), 2);
+        assert_eq!(svg.matches(r#"attributeName="height""#).count(), 0);
+        assert_eq!(svg.matches(r#"attributeName="y""#).count(), 1);
+        assert!(svg.contains(r#"keyTimes="0.00000;0.04348;0.08696""#));
+    }
+}
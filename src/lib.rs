@@ -0,0 +1,11 @@
+//! Sorting algorithms that report every step through a [`sort::Recorder`],
+//! the traces that the visualizer replays, and the renderers that draw
+//! them ([`visualization::Visualization`]).
+//!
+//! Elements are `i32` by default, but every algorithm also sorts any `Ord`
+//! type or any type with a comparator; counting and radix sort need an
+//! integer key ([`sort::IntKey`]).
+
+pub mod sort;
+pub mod utils;
+pub mod visualization;
@@ -1,40 +1,45 @@
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use std::time::Instant;
 
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+mod bench;
 mod command;
-mod sort;
-mod visualization;
-mod utils;
+mod export;
+
+use sorting_visualisation::{sort, utils, visualization};
 
-use command::parser::{CommandParser, Command};
-use visualization::{GraphVisualization, ListVisualization};
-use utils::ArrayUtils;
-use sort::*;
+use command::CommandParser;
+use command::parser::{Command, ExportFormat};
+use visualization::{Player, RaceView, Visualization};
+use utils::{ArrayUtils, Distribution};
+use sort::{AlgorithmSpec, Counters, Recorder, Stability, Tagged, Trace, check_stability};
 
 struct AppState {
     data: Vec<i32>,
     speed: f64,
-    visualization_mode: VisualizationMode,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum VisualizationMode {
-    List,
-    Graph,
-}
-
-impl Default for VisualizationMode {
-    fn default() -> Self {
-        VisualizationMode::List
-    }
+    /// Seed сессии; из него выводятся seed'ы отдельных генераций и перемешиваний
+    seed: u64,
+    seeds: StdRng,
+    /// Сортировать записи (ключ, исходная позиция) и проверять стабильность
+    check_stability: bool,
+    /// Последняя проигранная сортировка, её можно пересмотреть через `.play`
+    last_trace: Option<Trace>,
+    visualization: Box<dyn Visualization>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
+        let seed = rand::rng().random();
         Self {
             data: ArrayUtils::default_array(),
             speed: 1.0,
-            visualization_mode: VisualizationMode::default(),
+            seed,
+            seeds: StdRng::seed_from_u64(seed),
+            check_stability: false,
+            last_trace: None,
+            visualization: (visualization::RENDERERS[0].create)(),
         }
     }
 }
@@ -44,40 +49,106 @@ impl AppState {
         Self::default()
     }
 
+    /// Seed для очередной операции: заданный явно или следующий из seed'а сессии
+    fn next_seed(&mut self, seed: Option<u64>) -> u64 {
+        seed.unwrap_or_else(|| self.seeds.random())
+    }
+
+    /// Заменяет текущую визуализацию, например на собственную реализацию
+    fn set_visualization(&mut self, visualization: Box<dyn Visualization>) {
+        self.visualization = visualization;
+    }
+
     fn execute_command(&mut self, command: Command) -> Result<(), String> {
-        match command {
-            Command::Randomize => {
-                ArrayUtils::shuffle_array(&mut self.data);
-                println!("Массив перемешан случайным образом");
+        match command.with_seed(self.seed) {
+            Command::Randomize { seed } => {
+                let seed = self.next_seed(seed);
+                ArrayUtils::shuffle_array(&mut self.data, seed);
+                println!("Массив перемешан случайным образом (seed: {})", seed);
                 self.show_current_array();
             }
-            Command::Generate { count, range, no_repeats } => {
+            Command::Generate { count, range, no_repeats, distribution, seed } => {
                 let count = count.unwrap_or(10);
                 let (min, max) = range;
-                self.data = ArrayUtils::generate_random_array(count, min, max, no_repeats);
-                println!("Сгенерирован массив из {} элементов в диапазоне {}-{}", 
-                        count, min, max);
+                let seed = self.next_seed(seed);
+                self.data = ArrayUtils::generate_array(count, min, max, no_repeats, distribution, seed);
+                println!("Сгенерирован массив из {} элементов в диапазоне {}..{} (форма: {}, seed: {})", 
+                        count, min, max, distribution, seed);
                 self.show_current_array();
             }
-            Command::List => {
-                self.visualization_mode = VisualizationMode::List;
-                println!("Режим визуализации: список");
+            Command::Seed(Some(seed)) => {
+                self.seed = seed;
+                self.seeds = StdRng::seed_from_u64(seed);
+                println!("Seed сессии установлен: {}", seed);
+            }
+            Command::Seed(None) => {
+                println!("Seed сессии: {}", self.seed);
+            }
+            Command::Play(spec) => {
+                self.play_interactive(spec.as_ref())?;
+            }
+            Command::Stability(enabled) => {
+                self.check_stability = enabled.unwrap_or(!self.check_stability);
+                if !self.check_stability {
+                    println!("Проверка стабильности выключена");
+                    return Ok(());
+                }
+                println!("Проверка стабильности включена: сортируются пары (ключ, исходная позиция)");
+                if utils::has_unique_elements(&self.data) {
+                    // Без повторов проверять нечего, поэтому берём массив с частыми повторами
+                    let count = self.data.len().max(12);
+                    let seed = self.next_seed(None);
+                    self.data = ArrayUtils::generate_array(
+                        count, 0, (count / 3) as i32, false, Distribution::Uniform, seed,
+                    );
+                    println!("Сгенерирован массив из {} элементов с повторяющимися ключами (seed: {})",
+                             count, seed);
+                }
                 self.show_current_array();
             }
-            Command::Graphs => {
-                self.visualization_mode = VisualizationMode::Graph;
-                println!("Режим визуализации: графики");
+            Command::View(renderer) => {
+                self.set_visualization((renderer.create)());
+                println!("Режим визуализации: {}", renderer.description);
                 self.show_current_array();
             }
             Command::Speed(speed) => {
                 self.speed = speed;
                 println!("Скорость визуализации установлена на {:.2}x", speed);
             }
-            Command::Sort(sort_name) => {
-                self.run_sort_by_name(&sort_name)?;
-            }
-            Command::Compare(sort1, sort2) => {
-                self.compare_sorts(&sort1, &sort2)?;
+            Command::Sort(spec) => {
+                self.run_sort(&spec)?;
+            }
+            Command::Compare { algorithms, race: false } => {
+                self.compare_sorts(&algorithms)?;
+            }
+            Command::Compare { algorithms, race: true } => {
+                self.race_sorts(&algorithms)?;
+            }
+            Command::Bench { algorithms, sizes, reps, warmup, seed } => {
+                let seed = self.next_seed(seed);
+                self.bench_sorts(&algorithms, &sizes, reps, warmup, seed);
+            }
+            Command::Export { format, path } => {
+                self.export_run(format, &path)?;
+            }
+            Command::Record(Some(path)) => {
+                visualization::screen::start_recording(&path)
+                    .map_err(|e| format!("Ошибка записи файла {}: {}", path, e))?;
+                println!("Кадры записываются в {}, остановить: .record stop", path);
+            }
+            Command::Record(None) => match visualization::screen::stop_recording() {
+                Ok(Some(frames)) => println!("Запись сохранена ({} кадров)", frames),
+                Ok(None) => println!("Запись не ведётся"),
+                Err(e) => return Err(format!("Ошибка записи файла: {}", e)),
+            },
+            Command::Antiqsort { algorithm, count } => {
+                let count = count.unwrap_or(self.data.len());
+                self.data = utils::antiqsort(count, &algorithm).ok_or_else(|| {
+                    format!("{} не сравнивает элементы, противник на него не действует", algorithm.label())
+                })?;
+                println!("Сгенерирован худший вход для {} из {} элементов (противник МакИлроя)",
+                         algorithm.label(), count);
+                self.show_current_array();
             }
             Command::ReadList(filename) => {
                 match utils::ArrayUtils::read_array_from_file(&filename) {
@@ -91,21 +162,6 @@ impl AppState {
                     }
                 }
             }
-            Command::QuickSort => {
-                self.run_sort_by_name("quicksort")?;
-            }
-            Command::MergeSort => {
-                self.run_sort_by_name("mergesort")?;
-            }
-            Command::TimSort => {
-                self.run_sort_by_name("timsort")?;
-            }
-            Command::RadixSort => {
-                self.run_sort_by_name("radix")?;
-            }
-            Command::HeapSort => {
-                self.run_sort_by_name("heapsort")?;
-            }
         }
         Ok(())
     }
@@ -114,7 +170,7 @@ impl AppState {
         println!("Текущий массив: {}", utils::ArrayUtils::array_to_string(&self.data));
     }
 
-    fn run_sort_by_name(&mut self, name: &str) -> Result<(), String> {
+    fn run_sort(&mut self, spec: &AlgorithmSpec) -> Result<(), String> {
         let cloned_data = utils::ArrayUtils::clone_array(&self.data);
         
         if utils::ArrayUtils::is_trivial_array(&cloned_data) {
@@ -122,47 +178,45 @@ impl AppState {
             return Ok(());
         }
 
-        match name.to_lowercase().as_str() {
-            "quicksort" => {
-                self.run_sort_with_visualization(cloned_data, |arr, vis| {
-                    quicksort::quicksort_with_visualization(arr, vis)
-                }, "QuickSort")?;
-            }
-            "mergesort" => {
-                self.run_sort_with_visualization(cloned_data, |arr, vis| {
-                    mergesort::mergesort_with_visualization(arr, vis)
-                }, "MergeSort")?;
-            }
-            "timsort" => {
-                self.run_sort_with_visualization(cloned_data, |arr, vis| {
-                    timsort::timsort_with_visualization(arr, vis)
-                }, "TimSort")?;
-            }
-            "radix" => {
-                self.run_sort_with_visualization(cloned_data, |arr, vis| {
-                    radix::radix_sort_with_visualization(arr, vis)
-                }, "Radix Sort")?;
-            }
-            "heapsort" => {
-                self.run_sort_with_visualization(cloned_data, |arr, vis| {
-                    heapsort::heapsort_with_visualization(arr, vis)
-                }, "HeapSort")?;
-            }
-            "bubblesort" => {
-                self.run_sort_with_visualization(cloned_data, |arr, vis| {
-                    bubblesort::bubblesort_with_visualization(arr, vis)
-                }, "BubbleSort")?;
-            }
-            "insertionsort" => {
-                self.run_sort_with_visualization(cloned_data, |arr, vis| {
-                    insertionsort::insertion_sort_with_visualization(arr, vis)
-                }, "InsertionSort")?;
+        self.visualization.stats_mut().network = spec.network(cloned_data.len());
+        let title = match spec.label().split_once(' ') {
+            Some((_, options)) => format!("{} ({})", spec.algorithm.display_name, options),
+            None => spec.algorithm.display_name.to_string(),
+        };
+        if self.check_stability {
+            self.run_tagged_sort(spec, &title);
+            return Ok(());
+        }
+        self.run_sort_with_visualization(cloned_data, |rec| spec.run(rec), &title)
+    }
+
+    /// Сортирует записи с метками, показывает метки при проигрывании и
+    /// сообщает, сохранили ли равные ключи исходный порядок
+    fn run_tagged_sort(&mut self, spec: &AlgorithmSpec, sort_name: &str) {
+        println!("Запуск сортировки: {} (проверка стабильности)", sort_name);
+
+        let mut records = Tagged::from_keys(&self.data);
+        let trace = Trace::record_by(&mut records, Tagged::by_key, |rec| spec.run_tagged(rec));
+        let data: Vec<i32> = records.iter().map(|r| r.key).collect();
+        self.replay(|viz| viz.play_tagged(&trace), &data);
+        // `.play` и `.export` без аргументов берут эту сортировку, уже без меток
+        self.last_trace = Some(trace.map(|r| r.key));
+        self.data = data;
+
+        if utils::has_unique_elements(&self.data) {
+            println!("Стабильность: в массиве нет равных ключей, проверять нечего");
+            return;
+        }
+        match check_stability(&records) {
+            Stability::Stable => {
+                println!("Стабильность: стабильная — равные ключи сохранили исходный порядок");
             }
-            _ => {
-                return Err(format!("Неизвестный алгоритм сортировки: {}", name));
+            Stability::Unstable { position, first, second } => {
+                println!("Стабильность: нестабильная — {} оказался перед {} (позиции {} и {})",
+                         first, second, position, position + 1);
             }
         }
-        Ok(())
+        println!("Ожидается: {}", if spec.algorithm.stable { "стабильная" } else { "нестабильная" });
     }
 
     fn run_sort_with_visualization<F>(
@@ -172,72 +226,147 @@ impl AppState {
         sort_name: &str,
     ) -> Result<(), String>
     where
-        F: FnOnce(&mut Vec<i32>, &mut dyn visualization::Visualization),
+        F: FnOnce(&mut Recorder),
     {
         println!("Запуск сортировки: {}", sort_name);
-        
-        match self.visualization_mode {
-            VisualizationMode::List => {
-                let mut viz = ListVisualization::new(data.clone());
-                viz.set_speed(self.speed);
-                viz.reset_stats();
-                
-                let start_time = Instant::now();
-                sort_func(&mut data, &mut viz);
-                let duration = start_time.elapsed();
-                
-                viz.update_visual_time(duration.as_millis());
-                viz.final_render();
-            }
-            VisualizationMode::Graph => {
-                let mut viz = GraphVisualization::new(data.clone());
-                viz.set_speed(self.speed);
-                viz.reset_stats();
-                
-                let start_time = Instant::now();
-                sort_func(&mut data, &mut viz);
-                let duration = start_time.elapsed();
-                
-                viz.update_visual_time(duration.as_millis());
+
+        let trace = Trace::record(&mut data, sort_func);
+        self.replay(|viz| viz.play(&trace), &data);
+        self.last_trace = Some(trace);
+
+        // Обновляем основной массив
+        self.data = data;
+        Ok(())
+    }
+
+    /// Открывает трассу в интерактивном просмотре: новую сортировку выбранным
+    /// алгоритмом или, без алгоритма, последнюю проигранную
+    fn play_interactive(&mut self, spec: Option<&AlgorithmSpec>) -> Result<(), String> {
+        let trace = match spec {
+            Some(spec) => {
+                self.visualization.stats_mut().network = spec.network(self.data.len());
+                let mut data = utils::ArrayUtils::clone_array(&self.data);
+                let trace = Trace::record(&mut data, |rec| spec.run(rec));
+                self.data = data;
+                trace
+            }
+            None => self
+                .last_trace
+                .take()
+                .ok_or_else(|| "нет записанной сортировки, укажите алгоритм: .play <algorithm>".to_string())?,
+        };
+
+        // Повтор без алгоритма мог начаться после `.generate`, поэтому итог
+        // берётся из самой трассы, а не из текущего массива
+        let final_data = trace.final_data();
+        let mut result = Ok(());
+        if io::stdin().is_terminal() {
+            let viz = self.visualization.as_mut();
+            viz.reset_stats();
+            result = Player::new(&trace, self.speed).run(viz);
+            viz.set_speed(self.speed);
+            if result.is_ok() {
+                viz.stats_mut().counters = trace.counters;
+                viz.set_frame(final_data, Vec::new());
                 viz.final_render();
             }
+        } else {
+            // Без терминала клавиши не прочитать, поэтому просто проигрываем
+            self.replay(|viz| viz.play(&trace), &final_data);
         }
-        
-        // Обновляем основной массив
-        self.data = data;
+        // Трасса сохраняется и при ошибке терминала, чтобы её можно было открыть снова
+        self.last_trace = Some(trace);
+        result.map_err(|e| format!("ошибка терминала: {}", e))
+    }
+
+    /// Проигрывает записанную сортировку в текущей визуализации и рисует итог
+    fn replay<F>(&mut self, play: F, final_data: &[i32])
+    where
+        F: FnOnce(&mut dyn Visualization),
+    {
+        let viz = self.visualization.as_mut();
+        viz.set_speed(self.speed);
+        viz.reset_stats();
+
+        let start_time = Instant::now();
+        play(viz);
+        let duration = start_time.elapsed();
+
+        viz.update_visual_time(duration.as_millis());
+        viz.set_frame(final_data.to_vec(), Vec::new());
+        viz.final_render();
+    }
+
+    /// Сохраняет последнюю проигранную сортировку в файл
+    fn export_run(&self, format: ExportFormat, path: &str) -> Result<(), String> {
+        let trace = self
+            .last_trace
+            .as_ref()
+            .ok_or_else(|| "нет записанной сортировки, сначала выполните .sort или .play".to_string())?;
+        let frames = export::collect_frames(trace, export::MAX_FRAMES);
+        let frame_ms = export::frame_millis(self.speed);
+        let contents = match format {
+            ExportFormat::Svg => export::svg::render(
+                &frames,
+                frame_ms,
+                export::svg::DEFAULT_WIDTH,
+                export::svg::DEFAULT_HEIGHT,
+            )
+            .into_bytes(),
+            ExportFormat::Gif { size, fps } => {
+                let (width, height) = size.unwrap_or((export::gif::DEFAULT_WIDTH, export::gif::DEFAULT_HEIGHT));
+                let fps = fps.unwrap_or((1000 / frame_ms) as u16);
+                export::gif::render(&frames, width, height, fps)
+            }
+        };
+        std::fs::write(path, contents).map_err(|e| format!("Ошибка записи файла {}: {}", path, e))?;
+        println!("Анимация сохранена в {} ({} кадров)", path, frames.len());
         Ok(())
     }
 
-    fn compare_sorts(&self, sort1: &str, sort2: &str) -> Result<(), String> {
-        println!("Сравнение {} и {}:", sort1, sort2);
-        
-        let data1 = utils::ArrayUtils::clone_array(&self.data);
-        let data2 = utils::ArrayUtils::clone_array(&self.data);
+    fn compare_sorts(&self, specs: &[AlgorithmSpec]) -> Result<(), String> {
+        let labels: Vec<String> = specs.iter().map(AlgorithmSpec::label).collect();
+        println!("Сравнение {}:", labels.join(", "));
         
-        if utils::ArrayUtils::is_trivial_array(&data1) {
+        if utils::ArrayUtils::is_trivial_array(&self.data) {
             println!("Массив слишком мал для сравнения");
             return Ok(());
         }
 
-        // Тест первого алгоритма
-        let (time1, compares1, swaps1) = self.test_sort_performance(sort1, data1)?;
-        
-        // Тест второго алгоритма
-        let (time2, compares2, swaps2) = self.test_sort_performance(sort2, data2)?;
+        // Каждый алгоритм сортирует свою копию одного и того же массива
+        let mut results = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let data = utils::ArrayUtils::clone_array(&self.data);
+            results.push(self.test_sort_performance(spec, data)?);
+        }
 
         // Вывод результатов
+        let name_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0).max(15);
+        let line_width = name_width + 78;
         println!("\nРезультаты сравнения:");
-        println!("{}", "=".repeat(50));
-        println!("{:<15} {:<12} {:<10} {:<10}", "Алгоритм", "Время", "Сравнения", "Обмены");
-        println!("{}", "-".repeat(50));
-        println!("{:<15} {:<12} {:<10} {:<10}", sort1, format!("{}ms", time1), compares1, swaps1);
-        println!("{:<15} {:<12} {:<10} {:<10}", sort2, format!("{}ms", time2), compares2, swaps2);
-        println!("{}", "=".repeat(50));
-        
-        if time1 < time2 {
-            println!("{} быстрее на {}ms", sort1, time2 - time1);
-        } else if time2 < time1 {
-            println!("{} быстрее на {}ms", sort2, time1 - time2);
+        println!("{}", "=".repeat(line_width));
+        println!("{:<name_width$} {:<10} {:<10} {:<10} {:<10} {:<10} {:<12} {:<10}",
+                 "Алгоритм", "Время", "Сравнения", "Обмены", "Чтения", "Записи", "Доп. записи", "Доп. память");
+        println!("{}", "-".repeat(line_width));
+        for (name, (time, counters)) in labels.iter().zip(&results) {
+            println!("{:<name_width$} {:<10} {:<10} {:<10} {:<10} {:<10} {:<12} {:<10}",
+                     name, format!("{}ns", time), counters.compares, counters.swaps,
+                     counters.reads, counters.writes, counters.aux_writes, counters.aux_peak);
+        }
+        println!("{}", "=".repeat(line_width));
+        for (name, spec) in labels.iter().zip(specs) {
+            if let Some(network) = spec.network(self.data.len()) {
+                println!("{}: глубина сети {}, компараторов {}", name, network.depth(), network.comparators());
+            }
+        }
+
+        let mut by_time: Vec<(&String, u128)> =
+            labels.iter().zip(results.iter().map(|(time, _)| *time)).collect();
+        by_time.sort_by_key(|(_, time)| *time);
+        let (fastest, best) = by_time[0];
+        let (runner_up, second) = by_time[1];
+        if best < second {
+            println!("{} быстрее {} на {}ns", fastest, runner_up, second - best);
         } else {
             println!("Алгоритмы показали примерно одинаковое время");
         }
@@ -245,70 +374,130 @@ impl AppState {
         Ok(())
     }
 
-    fn test_sort_performance(&self, sort_name: &str, mut data: Vec<i32>) -> Result<(u128, usize, usize), String> {
-        let start_time = Instant::now();
-        let mut compares = 0;
-        let mut swaps = 0;
+    /// Гонка: алгоритмы проигрываются рядом на общих часах в шагах-операциях,
+    /// затем выводится обычная таблица сравнения
+    fn race_sorts(&self, specs: &[AlgorithmSpec]) -> Result<(), String> {
+        if utils::ArrayUtils::is_trivial_array(&self.data) {
+            println!("Массив слишком мал для сравнения");
+            return Ok(());
+        }
 
-        match sort_name.to_lowercase().as_str() {
-            "quicksort" => {
-                quicksort::quicksort(&mut data, &mut compares);
-            }
-            "mergesort" => {
-                mergesort::mergesort(&mut data, &mut compares);
-            }
-            "timsort" => {
-                timsort::timsort(&mut data, &mut compares);
-            }
-            "radix" => {
-                radix::radixsort(&mut data, &mut compares);
-            }
-            "heapsort" => {
-                heapsort::heapsort(&mut data, &mut compares);
-            }
-            "bubblesort" => {
-                sort::bubblesort::sort(&mut data, &mut compares);
-            }
-            "insertionsort" => {
-                sort::insertionsort::sort(&mut data, &mut compares);
-            }
-            _ => {
-                return Err(format!("Неизвестный алгоритм: {}", sort_name));
-            }
+        let runs = specs
+            .iter()
+            .map(|spec| {
+                let mut data = utils::ArrayUtils::clone_array(&self.data);
+                (spec.label(), Trace::record(&mut data, |rec| spec.run(rec)))
+            })
+            .collect();
+        let finish = RaceView::new(runs, self.speed).run();
+
+        println!("\nПорядок финиша:");
+        for (place, (label, steps)) in finish.iter().enumerate() {
+            println!("  {}. {} — {} шагов", place + 1, label, steps);
         }
+        println!();
+        self.compare_sorts(specs)
+    }
 
+    /// Для каждого размера генерирует один входной массив из `seed` и прогоняет
+    /// на нём все алгоритмы; без размеров используется текущий массив
+    fn bench_sorts(&self, specs: &[AlgorithmSpec], sizes: &[usize], reps: usize, warmup: usize, seed: u64) {
+        let inputs: Vec<Vec<i32>> = if sizes.is_empty() {
+            vec![self.data.clone()]
+        } else {
+            sizes
+                .iter()
+                .map(|&size| {
+                    ArrayUtils::generate_array(size, 0, size as i32, false, Distribution::Uniform, seed)
+                })
+                .collect()
+        };
+        if sizes.is_empty() {
+            println!("Бенчмарк: {} повторов, {} прогревочных запусков, текущий массив", reps, warmup);
+        } else {
+            println!("Бенчмарк: {} повторов, {} прогревочных запусков, seed: {}", reps, warmup, seed);
+        }
+
+        let results: Vec<bench::BenchResult> = inputs
+            .iter()
+            .flat_map(|input| specs.iter().map(move |spec| bench::measure(spec, input, warmup, reps)))
+            .collect();
+
+        let name_width = results.iter().map(|r| r.label.chars().count()).max().unwrap_or(0).max(15);
+        let line_width = name_width + 113;
+        println!("\nРезультаты бенчмарка (время в ns):");
+        println!("{}", "=".repeat(line_width));
+        println!("{:<name_width$} {:<8} {:<12} {:<12} {:<12} {:<12} {:<12} {:<12} {:<12} {:<12}",
+                 "Алгоритм", "Размер", "min", "median", "mean", "std dev", "p90", "p99", "max", "Сравнения");
+        println!("{}", "-".repeat(line_width));
+        for (i, result) in results.iter().enumerate() {
+            if i > 0 && i % specs.len() == 0 {
+                println!("{}", "-".repeat(line_width));
+            }
+            let stats = &result.stats;
+            println!("{:<name_width$} {:<8} {:<12} {:<12} {:<12.0} {:<12.0} {:<12} {:<12} {:<12} {:<12}",
+                     result.label, result.size, stats.min, stats.median, stats.mean,
+                     stats.std_dev, stats.p90, stats.p99, stats.max, result.counters.compares);
+        }
+        println!("{}", "=".repeat(line_width));
+    }
+
+    fn test_sort_performance(&self, spec: &AlgorithmSpec, mut data: Vec<i32>) -> Result<(u128, Counters), String> {
+        let start_time = Instant::now();
+        let counters = Trace::count(&mut data, |rec| spec.run(rec));
         let duration = start_time.elapsed();
-        Ok((duration.as_millis(), compares, swaps))
+
+        Ok((duration.as_nanos(), counters))
     }
 }
 
 fn show_help() {
     println!("Доступные команды:");
-    println!("  .randomize              - перемешать текущий массив");
-    println!("  .generate [count] [min-max] [nr] - генерировать случайный массив");
-    println!("  .list                   - режим визуализации: список");
-    println!("  .graphs                 - режим визуализации: столбцы");
+    println!("  .randomize [seed=<n>]   - перемешать текущий массив");
+    println!("  .seed [n]               - показать или задать seed сессии");
+    println!("  .stability [on|off]     - сортировать записи с метками и проверять стабильность");
+    println!("  .generate [count] [min-max] [nr] [shape=<name>] [k=<n>] [seed=<n>] - генерировать массив");
+    println!("                            формы: {}", utils::Distribution::NAMES.join(", "));
+    println!("  .antiqsort <algorithm> [count] - худший вход для алгоритма (противник МакИлроя)");
+    for renderer in visualization::RENDERERS {
+        println!("  {:<23} - режим визуализации: {}", format!(".{}", renderer.name), renderer.description);
+    }
     println!("  .speed <value>          - установить скорость визуализации");
     println!("  .sort <algorithm>       - сортировать выбранным алгоритмом");
-    println!("  .compare <alg1> <alg2>  - сравнить два алгоритма");
+    println!("  .play [algorithm]       - интерактивный просмотр: пауза, шаги, фазы, перемотка");
+    println!("  .compare <alg1> <alg2> [...] [race] - сравнить алгоритмы, race - гонка рядом");
     println!("  .readlist <filename>    - загрузить массив из файла");
-    println!("  .quicksort              - быстрая сортировка");
-    println!("  .mergesort              - сортировка слиянием");
-    println!("  .timsort                - сортировка Тима");
-    println!("  .radix                  - поразрядная сортировка");
-    println!("  .heapsort               - пирамидальная сортировка");
+    println!("  .export svg <file>      - сохранить последнюю сортировку как анимированный SVG");
+    println!("  .export gif <file> [width height fps] - то же в виде анимированного GIF");
+    println!("  .record <file.cast>     - записывать кадры в файл asciinema, .record stop - остановить");
+    println!("  .bench <alg...> [sizes=100,1000] [reps=5] [warmup=1] [seed=<n>] - замерить время");
+    for algorithm in sort::ALGORITHMS {
+        println!("  {:<23} - {}", format!(".{}", algorithm.name), algorithm.description);
+    }
     println!("  .help                   - показать эту справку");
     println!("  .exit                   - выход из программы");
     println!();
-    println!("Алгоритмы: quicksort, mergesort, timsort, radix, heapsort, bubblesort, insertionsort");
+    println!("Алгоритмы:");
+    for algorithm in sort::ALGORITHMS {
+        println!(
+            "  {:<15} {:<11} {:<11} {:<12} {}",
+            algorithm.name,
+            algorithm.complexity.average,
+            algorithm.complexity.worst,
+            if algorithm.stable { "стабильная" } else { "нестабильная" },
+            algorithm.aliases.join(", ")
+        );
+    }
 }
 
 fn main() {
+    visualization::init_colors();
     println!("=== Визуализатор алгоритмов сортировки ===");
     println!("Введите .help для получения списка команд");
     println!();
 
     let mut app = AppState::new();
+    println!("Seed сессии: {}", app.seed);
     app.show_current_array();
 
     loop {
@@ -325,6 +514,9 @@ fn main() {
 
                 match input {
                     ".exit" | ".quit" => {
+                        if let Err(e) = visualization::screen::stop_recording() {
+                            eprintln!("Ошибка записи файла: {}", e);
+                        }
                         println!("До свидания!");
                         break;
                     }
@@ -1,18 +1,22 @@
-fn bubblesort(arr: &mut [i32]) {
-    let len = arr.len();
-    let mut swapped;
-
-    for i in 0..len {
-        swapped = false;
-        for j in 0..(len - i - 1) {
-            if arr[j] > arr[j + 1] {
-                arr.swap(j, j + 1);
-                swapped = true;
-            }
-        }
-        // If no two elements were swapped by inner loop, then break
-        if !swapped {
-            break;
-        }
-    }
-}
+use super::trace::Recorder;
+
+pub fn bubblesort<T>(rec: &mut Recorder<T>) {
+    let len = rec.len();
+    let mut swapped;
+
+    for i in 0..len {
+        swapped = false;
+        for j in 0..(len - i - 1) {
+            if rec.compare(j, j + 1).is_gt() {
+                rec.swap(j, j + 1);
+                swapped = true;
+            }
+        }
+        rec.mark_sorted(len - i - 1..len - i);
+        // If no two elements were swapped by inner loop, then break
+        if !swapped {
+            rec.mark_sorted(0..len - i - 1);
+            break;
+        }
+    }
+}
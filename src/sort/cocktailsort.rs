@@ -1,35 +1,44 @@
-fn cocktail_sort(arr: &mut [i32]) {
-    let mut swapped = true;
-    let mut start = 0;
-    let mut end = arr.len() - 1;
-
-    while swapped {
-        swapped = false;
-
-        // Forward pass
-        for i in start..end {
-            if arr[i] > arr[i + 1] {
-                arr.swap(i, i + 1);
-                swapped = true;
-            }
-        }
-
-        if !swapped {
-            break;
-        }
-
-        swapped = false;
-        end -= 1;
-
-        // Backward pass
-        for i in (start..end).rev() {
-            if arr[i] > arr[i + 1] {
-                arr.swap(i, i + 1);
-                swapped = true;
-            }
-        }
-
-        start += 1;
-    }
-}
-
+use super::trace::Recorder;
+
+pub fn cocktail_sort<T>(rec: &mut Recorder<T>) {
+    if rec.len() < 2 {
+        rec.mark_sorted(0..rec.len());
+        return;
+    }
+
+    let mut swapped = true;
+    let mut start = 0;
+    let mut end = rec.len() - 1;
+
+    while swapped {
+        swapped = false;
+
+        // Forward pass
+        for i in start..end {
+            if rec.compare(i, i + 1).is_gt() {
+                rec.swap(i, i + 1);
+                swapped = true;
+            }
+        }
+        rec.mark_sorted(end..end + 1);
+
+        if !swapped {
+            break;
+        }
+
+        swapped = false;
+        end -= 1;
+
+        // Backward pass
+        for i in (start..end).rev() {
+            if rec.compare(i, i + 1).is_gt() {
+                rec.swap(i, i + 1);
+                swapped = true;
+            }
+        }
+        rec.mark_sorted(start..start + 1);
+
+        start += 1;
+    }
+    rec.mark_sorted(start..end + 1);
+}
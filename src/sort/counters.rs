@@ -0,0 +1,42 @@
+use std::fmt;
+
+use super::trace::SortEvent;
+
+/// Operation counts collected while an algorithm runs.
+///
+/// A swap is counted once in `swaps` and not as reads or writes; `writes`
+/// covers single-element assignments to the main array only.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub compares: usize,
+    pub swaps: usize,
+    pub reads: usize,
+    pub writes: usize,
+    pub aux_writes: usize,
+    /// Largest number of auxiliary elements allocated at the same time
+    pub aux_peak: usize,
+}
+
+impl Counters {
+    /// Updates the counts that can be derived from the event stream alone.
+    /// Reads and auxiliary memory are only known to the recorder.
+    pub fn record<T>(&mut self, event: &SortEvent<T>) {
+        match event {
+            SortEvent::Compare(..) => self.compares += 1,
+            SortEvent::Swap(..) => self.swaps += 1,
+            SortEvent::Write(..) => self.writes += 1,
+            SortEvent::AuxWrite(..) => self.aux_writes += 1,
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for Counters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "compares: {} | swaps: {} | reads: {} | writes: {} | aux writes: {} | aux peak: {}",
+            self.compares, self.swaps, self.reads, self.writes, self.aux_writes, self.aux_peak
+        )
+    }
+}
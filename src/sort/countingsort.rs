@@ -1,18 +1,63 @@
-fn counting_sort(arr: &mut [usize], max_value: usize) {
-    let mut count = vec![0; max_value + 1];
-
-    // Count occurrences of each value
-    for &num in arr.iter() {
-        count[num] += 1;
-    }
-
-    // Reconstruct the sorted array
-    let mut index = 0;
-    for (value, &freq) in count.iter().enumerate() {
-        for _ in 0..freq {
-            arr[index] = value;
-            index += 1;
-        }
-    }
-}
-
+use super::trace::Recorder;
+use super::{IntKey, radix};
+
+/// Largest key span counted directly is this many times the length, but
+/// never less than `MIN_SPAN`
+const SPAN_PER_ELEMENT: usize = 4;
+const MIN_SPAN: usize = 1 << 16;
+
+/// Stable counting sort over the whole `i32` key range.
+///
+/// Keys are shifted by the minimum, so negative numbers work, and the
+/// count buffer is as large as `max - min + 1`. Elements are scattered into
+/// an output buffer in input order, so equal keys keep their order.
+///
+/// When the span is much larger than the array (e.g. keys near both ends
+/// of `i32`), the buffer would not fit in memory, so the array is sorted
+/// with the equally stable LSD radix sort instead.
+pub fn counting_sort<T: Clone + IntKey>(rec: &mut Recorder<T>) {
+    let n = rec.len();
+    let Some((min, max)) = (0..n).map(|i| rec.get(i).key()).fold(None, |acc, key| match acc {
+        None => Some((key, key)),
+        Some((min, max)) => Some((key.min(min), key.max(max))),
+    }) else {
+        return;
+    };
+    let slot = |key: i32| (i64::from(key) - i64::from(min)) as usize;
+    if slot(max) >= (n * SPAN_PER_ELEMENT).max(MIN_SPAN) {
+        rec.phase("key span too large, radix fallback");
+        radix::radix_lsd(rec, 256);
+        return;
+    }
+    let mut count = vec![0usize; slot(max) + 1];
+    rec.aux_alloc(count.len() + n);
+
+    // Count occurrences of each key
+    rec.phase("count");
+    for i in 0..n {
+        count[slot(rec.get(i).key())] += 1;
+    }
+
+    // Turn counts into the first output position of each key
+    let mut start = 0;
+    for c in count.iter_mut() {
+        (*c, start) = (start, start + *c);
+    }
+
+    rec.phase("scatter");
+    let mut output: Vec<Option<T>> = vec![None; n];
+    for i in 0..n {
+        let value = rec.get(i);
+        let s = slot(value.key());
+        output[count[s]] = Some(value.clone());
+        rec.aux_write(count[s], value);
+        count[s] += 1;
+    }
+
+    rec.phase("copy back");
+    for (i, value) in output.into_iter().flatten().enumerate() {
+        rec.write(i, value);
+    }
+    rec.aux_free(count.len() + n);
+    rec.mark_sorted(0..n);
+}
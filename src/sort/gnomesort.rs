@@ -1,13 +1,15 @@
-fn gnome_sort(arr: &mut [i32]) {
-    let mut index = 0;
-
-    while index < arr.len() {
-        if index == 0 || arr[index] >= arr[index - 1] {
-            index += 1;
-        } else {
-            arr.swap(index, index - 1);
-            index -= 1;
-        }
-    }
-}
-
+use super::trace::Recorder;
+
+pub fn gnome_sort<T>(rec: &mut Recorder<T>) {
+    let mut index = 0;
+
+    while index < rec.len() {
+        if index == 0 || rec.compare(index, index - 1).is_ge() {
+            index += 1;
+        } else {
+            rec.swap(index, index - 1);
+            index -= 1;
+        }
+    }
+    rec.mark_sorted(0..rec.len());
+}
@@ -1,36 +1,47 @@
-pub fn heapsort<F>(arr: &mut [i32], visualize: &F)
-where
-    F: Fn(&[i32], &[usize]),
-{
-    let n = arr.len();
-    for i in (0..n / 2).rev() {
-        heapify(arr, n, i, visualize);
-    }
-
-    for i in (1..n).rev() {
-        arr.swap(0, i);
-        visualize(arr, &[0, i]);
-        heapify(&mut arr[..i], i, 0, visualize);
-    }
-}
-
-fn heapify<F>(arr: &mut [i32], n: usize, i: usize, visualize: &F)
-where
-    F: Fn(&[i32], &[usize]),
-{
-    let mut largest = i;
-    let left = 2 * i + 1;
-    let right = 2 * i + 2;
-
-    if left < n && arr[left] > arr[largest] {
-        largest = left;
-    }
-    if right < n && arr[right] > arr[largest] {
-        largest = right;
-    }
-    if largest != i {
-        arr.swap(i, largest);
-        visualize(arr, &[i, largest]);
-        heapify(arr, n, largest, visualize);
-    }
-}
+use super::trace::Recorder;
+
+pub fn heapsort<T>(rec: &mut Recorder<T>) {
+    let n = rec.len();
+    rec.phase("build heap");
+    build_heap(rec, 0, n);
+
+    rec.phase("extract max");
+    extract_max(rec, 0, n);
+}
+
+/// Turns `lo..hi` into a max-heap rooted at `lo`
+pub(super) fn build_heap<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) {
+    let n = hi - lo;
+    for i in (0..n / 2).rev() {
+        heapify(rec, lo, n, i);
+    }
+}
+
+/// Moves the maximum of the heap in `lo..hi` to the end until it is sorted
+pub(super) fn extract_max<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) {
+    let n = hi - lo;
+    for i in (1..n).rev() {
+        rec.swap(lo, lo + i);
+        rec.mark_sorted(lo + i..lo + i + 1);
+        heapify(rec, lo, i, 0);
+    }
+    rec.mark_sorted(lo..lo + n.min(1));
+}
+
+/// Sifts node `i` down the heap of `n` elements stored from `lo`
+fn heapify<T>(rec: &mut Recorder<T>, lo: usize, n: usize, i: usize) {
+    let mut largest = i;
+    let left = 2 * i + 1;
+    let right = 2 * i + 2;
+
+    if left < n && rec.compare(lo + left, lo + largest).is_gt() {
+        largest = left;
+    }
+    if right < n && rec.compare(lo + right, lo + largest).is_gt() {
+        largest = right;
+    }
+    if largest != i {
+        rec.swap(lo + i, lo + largest);
+        heapify(rec, lo, n, largest);
+    }
+}
@@ -1,11 +1,18 @@
-fn insertion_sort<T: Ord>(arr: &mut [T]) {
-    let len = arr.len();
-    for i in 1..len {
-        let mut j = i;
-        while j > 0 && arr[j] < arr[j - 1] {
-            arr.swap(j, j - 1);
-            j -= 1;
-        }
-    }
-}
-
+use super::trace::Recorder;
+
+pub fn insertion_sort<T>(rec: &mut Recorder<T>) {
+    let len = rec.len();
+    sort_range(rec, 0, len);
+}
+
+/// Insertion sort of `lo..hi`, used by hybrid sorts for short slices
+pub(super) fn sort_range<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) {
+    for i in lo + 1..hi {
+        let mut j = i;
+        while j > lo && rec.compare(j, j - 1).is_lt() {
+            rec.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+    rec.mark_sorted(lo..hi);
+}
@@ -0,0 +1,83 @@
+use super::trace::{PhaseTracker, Recorder};
+use super::{heapsort, insertionsort, quicksort};
+
+/// Slices this short are finished with insertion sort
+const INSERTION_THRESHOLD: usize = 16;
+
+/// Introsort: quicksort with a median-of-three pivot that falls back to
+/// heapsort once the recursion gets deeper than `2·log2(n)`, so the worst
+/// case stays O(n log n), and hands short slices to insertion sort.
+///
+/// Every switch between the three strategies is recorded as a phase.
+pub fn introsort<T>(rec: &mut Recorder<T>) {
+    let len = rec.len();
+    let depth_limit = 2 * len.max(1).ilog2() as usize;
+    let mut sorter = IntroSort { phase: PhaseTracker::default() };
+    sorter.sort_range(rec, 0, len, depth_limit);
+}
+
+struct IntroSort {
+    phase: PhaseTracker,
+}
+
+impl IntroSort {
+    fn sort_range<T>(&mut self, rec: &mut Recorder<T>, lo: usize, hi: usize, depth: usize) {
+        if hi - lo <= INSERTION_THRESHOLD {
+            self.phase.switch(rec, "insertion sort");
+            insertionsort::sort_range(rec, lo, hi);
+            return;
+        }
+        if depth == 0 {
+            self.phase.switch(rec, "heapsort fallback");
+            heapsort::build_heap(rec, lo, hi);
+            heapsort::extract_max(rec, lo, hi);
+            return;
+        }
+
+        self.phase.switch(rec, "quicksort");
+        median_of_three(rec, lo, hi);
+        let pivot = quicksort::partition(rec, lo, hi);
+        rec.mark_sorted(pivot..pivot + 1);
+        self.sort_range(rec, lo, pivot, depth - 1);
+        self.sort_range(rec, pivot + 1, hi, depth - 1);
+    }
+}
+
+/// Orders the first, middle and last elements so that the median of the
+/// three ends up last, where `partition` takes its pivot from
+fn median_of_three<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) {
+    let mid = lo + (hi - lo) / 2;
+    let last = hi - 1;
+    if rec.compare(mid, lo).is_lt() {
+        rec.swap(mid, lo);
+    }
+    if rec.compare(last, lo).is_lt() {
+        rec.swap(last, lo);
+    }
+    // `lo` now holds the smallest, so the median is the smaller of the other two
+    if rec.compare(mid, last).is_lt() {
+        rec.swap(mid, last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{SortEvent, Trace, shaped_inputs};
+    use crate::utils::{Distribution, is_sorted};
+
+    #[test]
+    fn test_introsort_stays_n_log_n() {
+        let n = 4096;
+        let shapes = [Distribution::Sorted, Distribution::Reversed, Distribution::OrganPipe, Distribution::AllEqual];
+        for (label, mut data) in shaped_inputs(&shapes, &[n], (0, 1000)) {
+            let trace = Trace::record(&mut data, introsort);
+            assert!(is_sorted(&data), "{}", label);
+            let bound = 4 * n * n.ilog2() as usize;
+            assert!(trace.counters.compares < bound, "{}: {} compares", label, trace.counters.compares);
+            let phases: Vec<&SortEvent> =
+                trace.events.iter().filter(|e| matches!(e, SortEvent::PhaseStart(_))).collect();
+            assert!(phases.contains(&&SortEvent::PhaseStart("insertion sort".into())), "{}", label);
+        }
+    }
+}
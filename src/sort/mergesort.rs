@@ -1,39 +1,43 @@
-pub fn mergesort<F>(arr: &mut [i32], visualize: &F)
-where
-    F: Fn(&[i32], &[usize]),
-{
-    if arr.len() > 1 {
-        let mid = arr.len() / 2;
-        mergesort(&mut arr[..mid], visualize);
-        mergesort(&mut arr[mid..], visualize);
-        merge(arr, mid, visualize);
-    }
-}
-
-fn merge<F>(arr: &mut [i32], mid: usize, visualize: &F)
-where
-    F: Fn(&[i32], &[usize]),
-{
-    let mut merged = Vec::with_capacity(arr.len());
-    let (left, right) = arr.split_at(mid);
-    let (mut i, mut j) = (0, 0);
-
-    while i < left.len() && j < right.len() {
-        if left[i] <= right[j] {
-            merged.push(left[i]);
-            i += 1;
-        } else {
-            merged.push(right[j]);
-            j += 1;
-        }
-    }
-
-    merged.extend_from_slice(&left[i..]);
-    merged.extend_from_slice(&right[j..]);
-
-    for (k, &val) in merged.iter().enumerate() {
-        arr[k] = val;
-    }
-
-    visualize(arr, &(0..arr.len()).collect::<Vec<_>>());
-}
+use super::trace::Recorder;
+
+pub fn mergesort<T: Clone>(rec: &mut Recorder<T>) {
+    let len = rec.len();
+    sort_range(rec, 0, len);
+    rec.mark_sorted(0..len);
+}
+
+fn sort_range<T: Clone>(rec: &mut Recorder<T>, lo: usize, hi: usize) {
+    if hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        sort_range(rec, lo, mid);
+        sort_range(rec, mid, hi);
+        merge(rec, lo, mid, hi);
+    }
+}
+
+fn merge<T: Clone>(rec: &mut Recorder<T>, lo: usize, mid: usize, hi: usize) {
+    let mut merged = Vec::with_capacity(hi - lo);
+    rec.aux_alloc(hi - lo);
+    let (mut i, mut j) = (lo, mid);
+
+    while i < mid && j < hi {
+        if rec.compare(i, j).is_le() {
+            merged.push(rec.get(i));
+            i += 1;
+        } else {
+            merged.push(rec.get(j));
+            j += 1;
+        }
+        rec.aux_write(merged.len() - 1, merged[merged.len() - 1].clone());
+    }
+
+    for k in (i..mid).chain(j..hi) {
+        merged.push(rec.get(k));
+        rec.aux_write(merged.len() - 1, rec.get(k));
+    }
+
+    for (k, val) in merged.into_iter().enumerate() {
+        rec.write(lo + k, val);
+    }
+    rec.aux_free(hi - lo);
+}
@@ -1,18 +1,597 @@
-pub mod quicksort;
-pub mod mergesort;
-pub mod timsort;
-pub mod radix;
-pub mod heapsort;
-pub mod bubblesort;
-pub mod selectionsort;
-pub mod insertionsort;
-pub mod gnomesort;
-pub mod countingsort;
-pub mod cocktailsort;
-pub mod shellsort; 
-
-pub use self::quicksort::quicksort;
-pub use self::mergesort::mergesort;
-pub use self::timsort::timsort;
-pub use self::radix::radixsort;
-pub use self::heapsort::heapsort;
+pub mod quicksort;
+pub mod mergesort;
+pub mod timsort;
+pub mod radix;
+pub mod heapsort;
+pub mod introsort;
+pub mod pdqsort;
+pub mod network;
+pub mod bubblesort;
+pub mod selectionsort;
+pub mod insertionsort;
+pub mod gnomesort;
+pub mod countingsort;
+pub mod cocktailsort;
+pub mod shellsort;
+pub mod counters;
+pub mod stability;
+pub mod trace;
+mod xorshift;
+
+pub use self::quicksort::quicksort;
+pub use self::mergesort::mergesort;
+pub use self::timsort::timsort;
+pub use self::heapsort::heapsort;
+pub use self::introsort::introsort;
+pub use self::pdqsort::pdqsort;
+pub use self::network::{Network, bitonic_sort, odd_even_merge_sort};
+pub use self::bubblesort::bubblesort;
+pub use self::insertionsort::insertion_sort;
+pub use self::selectionsort::selection_sort;
+pub use self::gnomesort::gnome_sort;
+pub use self::countingsort::counting_sort;
+pub use self::cocktailsort::cocktail_sort;
+pub use self::shellsort::shell_sort;
+pub use self::counters::Counters;
+pub use self::stability::{Stability, Tagged, check_stability};
+pub use self::trace::{Recorder, SortEvent, Trace};
+
+/// Integer key of an element, used by the distribution sorts (counting,
+/// radix) that place elements by key instead of comparing them
+pub trait IntKey {
+    fn key(&self) -> i32;
+}
+
+impl IntKey for i32 {
+    fn key(&self) -> i32 {
+        *self
+    }
+}
+
+/// Average and worst-case running time of an algorithm
+#[derive(Debug, Clone, Copy)]
+pub struct Complexity {
+    pub average: &'static str,
+    pub worst: &'static str,
+}
+
+/// An option an algorithm understands, with every value it accepts
+#[derive(Debug)]
+pub struct OptionSpec {
+    pub key: &'static str,
+    pub values: &'static [&'static str],
+    pub default: &'static str,
+}
+
+/// Option values chosen for one run, e.g. `base=16`, and the seed for
+/// algorithms that make random choices
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SortOptions {
+    values: Vec<(&'static str, &'static str)>,
+    seed: Option<u64>,
+}
+
+impl SortOptions {
+    pub fn get(&self, key: &str) -> Option<&'static str> {
+        self.values.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    fn set(&mut self, key: &'static str, value: &'static str) {
+        match self.values.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((key, value)),
+        }
+    }
+}
+
+/// An entry of the algorithm registry.
+///
+/// Command parsing, help text, `.sort` and `.compare` all look algorithms
+/// up here, so adding an entry to [`ALGORITHMS`] is enough to expose it.
+#[derive(Debug)]
+pub struct Algorithm {
+    /// Canonical lowercase name, also usable as a `.name` shortcut command
+    pub name: &'static str,
+    pub display_name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub description: &'static str,
+    pub complexity: Complexity,
+    pub stable: bool,
+    pub options: &'static [OptionSpec],
+    /// Entry point; `SortOptions` holds a value for every key in `options`
+    pub run: fn(&mut Recorder, &SortOptions),
+    /// The same algorithm on tagged records, for stability checks
+    pub run_tagged: fn(&mut Recorder<Tagged>, &SortOptions),
+    /// Comparator network for an array of the given length, for the
+    /// data-oblivious algorithms that are sorting networks
+    pub network: Option<fn(usize) -> Network>,
+}
+
+impl Algorithm {
+    /// Checks the name and aliases, ignoring case
+    pub fn matches(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+
+    pub fn default_options(&self) -> SortOptions {
+        let mut options = SortOptions::default();
+        for spec in self.options {
+            options.set(spec.key, spec.default);
+        }
+        options
+    }
+}
+
+/// An algorithm from the registry together with the options to run it with
+#[derive(Debug, Clone)]
+pub struct AlgorithmSpec {
+    pub algorithm: &'static Algorithm,
+    pub options: SortOptions,
+}
+
+impl AlgorithmSpec {
+    pub fn new(algorithm: &'static Algorithm) -> Self {
+        Self {
+            algorithm,
+            options: algorithm.default_options(),
+        }
+    }
+
+    /// Applies one option, written either as `key=value` or as a bare value
+    /// such as `msd` when exactly one option accepts it.
+    pub fn set_option(&mut self, arg: &str) -> Result<(), String> {
+        let (key, value) = match arg.split_once('=') {
+            Some((key, value)) => (Some(key), value),
+            None => (None, arg),
+        };
+        let spec = self
+            .algorithm
+            .options
+            .iter()
+            .filter(|spec| key.is_none_or(|key| spec.key.eq_ignore_ascii_case(key)))
+            .find(|spec| spec.values.iter().any(|v| v.eq_ignore_ascii_case(value)))
+            .ok_or_else(|| format!("{} does not accept option {}", self.algorithm.name, arg))?;
+        let value = spec
+            .values
+            .iter()
+            .find(|v| v.eq_ignore_ascii_case(value))
+            .copied()
+            .unwrap_or(spec.default);
+        self.options.set(spec.key, value);
+        Ok(())
+    }
+
+    /// Seeds the random choices of the algorithm, such as `pivot=random`
+    pub fn set_seed(&mut self, seed: u64) {
+        self.options.seed = Some(seed);
+    }
+
+    pub fn run(&self, rec: &mut Recorder) {
+        (self.algorithm.run)(rec, &self.options)
+    }
+
+    pub fn run_tagged(&self, rec: &mut Recorder<Tagged>) {
+        (self.algorithm.run_tagged)(rec, &self.options)
+    }
+
+    /// The comparator network for `len` elements, if the algorithm is one
+    pub fn network(&self, len: usize) -> Option<Network> {
+        self.algorithm.network.map(|network| network(len))
+    }
+
+    /// Algorithm name followed by the options that differ from the defaults
+    pub fn label(&self) -> String {
+        let mut label = self.algorithm.name.to_string();
+        for spec in self.algorithm.options {
+            if let Some(value) = self.options.get(spec.key).filter(|v| *v != spec.default) {
+                label.push_str(&format!(" {}={}", spec.key, value));
+            }
+        }
+        label
+    }
+}
+
+pub const ALGORITHMS: &[Algorithm] = &[
+    Algorithm {
+        name: "quicksort",
+        display_name: "QuickSort",
+        aliases: &["quick"],
+        description: "быстрая сортировка (partition=lomuto|hoare|threeway|dualpivot, pivot=...)",
+        complexity: Complexity { average: "O(n log n)", worst: "O(n²)" },
+        stable: false,
+        options: &[
+            OptionSpec {
+                key: "partition",
+                values: &["lomuto", "hoare", "threeway", "dualpivot"],
+                default: "lomuto",
+            },
+            OptionSpec {
+                key: "pivot",
+                values: &["last", "first", "middle", "random", "median3", "ninther"],
+                default: "last",
+            },
+        ],
+        run: quicksort::run,
+        run_tagged: quicksort::run,
+        network: None,
+    },
+    Algorithm {
+        name: "mergesort",
+        display_name: "MergeSort",
+        aliases: &["merge"],
+        description: "сортировка слиянием",
+        complexity: Complexity { average: "O(n log n)", worst: "O(n log n)" },
+        stable: true,
+        options: &[],
+        run: |rec, _| mergesort(rec),
+        run_tagged: |rec, _| mergesort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "timsort",
+        display_name: "TimSort",
+        aliases: &["tim"],
+        description: "сортировка Тима",
+        complexity: Complexity { average: "O(n log n)", worst: "O(n log n)" },
+        stable: true,
+        options: &[],
+        run: |rec, _| timsort(rec),
+        run_tagged: |rec, _| timsort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "radix",
+        display_name: "Radix Sort",
+        aliases: &["radixsort"],
+        description: "поразрядная сортировка (lsd/msd, base=2|4|10|16|256)",
+        complexity: Complexity { average: "O(n·k)", worst: "O(n·k)" },
+        stable: true,
+        options: &[
+            OptionSpec { key: "variant", values: &["lsd", "msd"], default: "lsd" },
+            OptionSpec { key: "base", values: &["2", "4", "10", "16", "256"], default: "10" },
+        ],
+        run: radix::run,
+        run_tagged: radix::run,
+        network: None,
+    },
+    Algorithm {
+        name: "heapsort",
+        display_name: "HeapSort",
+        aliases: &["heap"],
+        description: "пирамидальная сортировка",
+        complexity: Complexity { average: "O(n log n)", worst: "O(n log n)" },
+        stable: false,
+        options: &[],
+        run: |rec, _| heapsort(rec),
+        run_tagged: |rec, _| heapsort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "introsort",
+        display_name: "IntroSort",
+        aliases: &["intro"],
+        description: "интроспективная сортировка (quick + heap + insertion)",
+        complexity: Complexity { average: "O(n log n)", worst: "O(n log n)" },
+        stable: false,
+        options: &[],
+        run: |rec, _| introsort(rec),
+        run_tagged: |rec, _| introsort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "pdqsort",
+        display_name: "PdqSort",
+        aliases: &["pdq"],
+        description: "pattern-defeating quicksort, основа прежнего sort_unstable в Rust",
+        complexity: Complexity { average: "O(n log n)", worst: "O(n log n)" },
+        stable: false,
+        options: &[],
+        run: |rec, _| pdqsort(rec),
+        run_tagged: |rec, _| pdqsort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "bitonic",
+        display_name: "Bitonic Sort",
+        aliases: &["bitonicsort"],
+        description: "битоническая сеть сортировки, не зависит от данных",
+        complexity: Complexity { average: "O(n log² n)", worst: "O(n log² n)" },
+        stable: false,
+        options: &[],
+        run: |rec, _| bitonic_sort(rec),
+        run_tagged: |rec, _| bitonic_sort(rec),
+        network: Some(Network::bitonic),
+    },
+    Algorithm {
+        name: "oddeven",
+        display_name: "Odd-Even MergeSort",
+        aliases: &["batcher", "oddevenmerge"],
+        description: "чёт-нечётная сеть слияния Бэтчера, не зависит от данных",
+        complexity: Complexity { average: "O(n log² n)", worst: "O(n log² n)" },
+        stable: false,
+        options: &[],
+        run: |rec, _| odd_even_merge_sort(rec),
+        run_tagged: |rec, _| odd_even_merge_sort(rec),
+        network: Some(Network::odd_even_merge),
+    },
+    Algorithm {
+        name: "bubblesort",
+        display_name: "BubbleSort",
+        aliases: &["bubble"],
+        description: "сортировка пузырьком",
+        complexity: Complexity { average: "O(n²)", worst: "O(n²)" },
+        stable: true,
+        options: &[],
+        run: |rec, _| bubblesort(rec),
+        run_tagged: |rec, _| bubblesort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "insertionsort",
+        display_name: "InsertionSort",
+        aliases: &["insertion"],
+        description: "сортировка вставками",
+        complexity: Complexity { average: "O(n²)", worst: "O(n²)" },
+        stable: true,
+        options: &[],
+        run: |rec, _| insertion_sort(rec),
+        run_tagged: |rec, _| insertion_sort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "selectionsort",
+        display_name: "SelectionSort",
+        aliases: &["selection"],
+        description: "сортировка выбором",
+        complexity: Complexity { average: "O(n²)", worst: "O(n²)" },
+        stable: false,
+        options: &[],
+        run: |rec, _| selection_sort(rec),
+        run_tagged: |rec, _| selection_sort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "cocktailsort",
+        display_name: "CocktailSort",
+        aliases: &["cocktail", "shakersort"],
+        description: "шейкерная сортировка",
+        complexity: Complexity { average: "O(n²)", worst: "O(n²)" },
+        stable: true,
+        options: &[],
+        run: |rec, _| cocktail_sort(rec),
+        run_tagged: |rec, _| cocktail_sort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "gnomesort",
+        display_name: "GnomeSort",
+        aliases: &["gnome"],
+        description: "гномья сортировка",
+        complexity: Complexity { average: "O(n²)", worst: "O(n²)" },
+        stable: true,
+        options: &[],
+        run: |rec, _| gnome_sort(rec),
+        run_tagged: |rec, _| gnome_sort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "shellsort",
+        display_name: "ShellSort",
+        aliases: &["shell"],
+        description: "сортировка Шелла",
+        complexity: Complexity { average: "O(n^1.5)", worst: "O(n²)" },
+        stable: false,
+        options: &[],
+        run: |rec, _| shell_sort(rec),
+        run_tagged: |rec, _| shell_sort(rec),
+        network: None,
+    },
+    Algorithm {
+        name: "countingsort",
+        display_name: "CountingSort",
+        aliases: &["counting"],
+        description: "сортировка подсчётом",
+        complexity: Complexity { average: "O(n + k)", worst: "O(n + k)" },
+        stable: true,
+        options: &[],
+        run: |rec, _| counting_sort(rec),
+        run_tagged: |rec, _| counting_sort(rec),
+        network: None,
+    },
+];
+
+/// Looks an algorithm up by name or alias, ignoring case
+pub fn find(name: &str) -> Option<&'static Algorithm> {
+    ALGORITHMS.iter().find(|algorithm| algorithm.matches(name))
+}
+
+/// Test inputs for the algorithm modules: one array of values in `range`
+/// per shape and length, seeded by the length, with a label for messages
+#[cfg(test)]
+pub(crate) fn shaped_inputs(
+    shapes: &[crate::utils::Distribution],
+    sizes: &[usize],
+    range: (i32, i32),
+) -> Vec<(String, Vec<i32>)> {
+    let mut inputs = Vec::new();
+    for &shape in shapes {
+        for &n in sizes {
+            let data = crate::utils::ArrayUtils::generate_array(n, range.0, range.1, false, shape, n as u64);
+            inputs.push((format!("{} n={}", shape, n), data));
+        }
+    }
+    inputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{generate_random_list, is_sorted};
+
+    #[test]
+    fn test_every_algorithm_sorts() {
+        let input = generate_random_list(Some(64), 0, 50, false);
+        for algorithm in ALGORITHMS {
+            let mut data = input.clone();
+            let spec = AlgorithmSpec::new(algorithm);
+            Trace::record(&mut data, |rec| spec.run(rec));
+            assert!(is_sorted(&data), "{} left {:?}", algorithm.name, data);
+        }
+    }
+
+    #[test]
+    fn test_cocktail_sort_marks_every_position() {
+        // The last backward pass swaps nothing, which ends the sort
+        let mut data = vec![2, 1, 3, 4];
+        let trace = Trace::record(&mut data, cocktail_sort);
+        let mut sorted = vec![false; data.len()];
+        for event in &trace.events {
+            if let SortEvent::MarkSorted(range) = event {
+                sorted[range.clone()].fill(true);
+            }
+        }
+        assert!(sorted.iter().all(|&done| done), "{:?}", sorted);
+    }
+
+    #[test]
+    fn test_radix_variants_handle_negatives() {
+        let input = generate_random_list(Some(200), -100_000, 100_000, false);
+        let mut expected = input.clone();
+        expected.sort();
+        for variant in ["lsd", "msd"] {
+            for base in ["2", "4", "10", "16", "256"] {
+                let mut spec = AlgorithmSpec::new(find("radix").unwrap());
+                spec.set_option(variant).unwrap();
+                spec.set_option(&format!("base={}", base)).unwrap();
+                let mut data = input.clone();
+                Trace::record(&mut data, |rec| spec.run(rec));
+                assert_eq!(data, expected, "{}", spec.label());
+            }
+        }
+        let mut extremes = vec![i32::MAX, 0, i32::MIN, -1, 1];
+        Trace::record(&mut extremes, |rec| radix::radix_msd(rec, 16));
+        assert_eq!(extremes, vec![i32::MIN, -1, 0, 1, i32::MAX]);
+    }
+
+    #[test]
+    fn test_set_option_rejects_unknown_values() {
+        let mut spec = AlgorithmSpec::new(find("radix").unwrap());
+        assert!(spec.set_option("base=3").is_err());
+        assert!(spec.set_option("pivot=first").is_err());
+        spec.set_option("MSD").unwrap();
+        assert_eq!(spec.label(), "radix variant=msd");
+    }
+
+    #[test]
+    fn test_counting_sort_handles_negatives() {
+        let mut data = vec![3, -7, 0, -12, -7, 2];
+        Trace::record(&mut data, counting_sort);
+        assert_eq!(data, vec![-12, -7, -7, 0, 2, 3]);
+    }
+
+    #[test]
+    fn test_counting_sort_handles_extreme_keys() {
+        let mut data = vec![-2_000_000_000, 2_000_000_000, 5, i32::MIN, i32::MAX, 5];
+        let trace = Trace::record(&mut data, counting_sort);
+        assert_eq!(data, vec![i32::MIN, -2_000_000_000, 5, 5, 2_000_000_000, i32::MAX]);
+        // The radix fallback needs buffers of the array's size, not the key span's
+        assert!(trace.counters.aux_peak < 1000, "{}", trace.counters.aux_peak);
+    }
+
+    /// Runs every comparison sort on `input` ordered by `cmp`
+    fn check_generic<T: Clone + Default + std::fmt::Debug + PartialEq>(
+        input: &[T],
+        cmp: impl Fn(&T, &T) -> std::cmp::Ordering + Copy,
+    ) {
+        let mut expected = input.to_vec();
+        expected.sort_by(cmp);
+        type SortFn<T> = fn(&mut Recorder<T>);
+        let sorts: [(&str, SortFn<T>); 14] = [
+            ("quicksort", quicksort),
+            ("introsort", introsort),
+            ("pdqsort", pdqsort),
+            ("bitonic", bitonic_sort),
+            ("oddeven", odd_even_merge_sort),
+            ("mergesort", mergesort),
+            ("timsort", timsort),
+            ("heapsort", heapsort),
+            ("bubblesort", bubblesort),
+            ("insertion", insertion_sort),
+            ("selection", selection_sort),
+            ("gnome", gnome_sort),
+            ("cocktail", cocktail_sort),
+            ("shell", shell_sort),
+        ];
+        for (name, sort) in sorts {
+            let mut data = input.to_vec();
+            let trace = Trace::record_by(&mut data, cmp, sort);
+            assert!(
+                data.windows(2).all(|w| cmp(&w[0], &w[1]).is_le()),
+                "{} left {:?}",
+                name,
+                data
+            );
+            // Replaying the trace gives the same array
+            let mut replayed = trace.initial.clone();
+            let mut aux = Vec::new();
+            for event in &trace.events {
+                event.apply(&mut replayed, &mut aux);
+            }
+            assert_eq!(replayed, data, "{}", name);
+        }
+        let mut data = input.to_vec();
+        Trace::record_by(&mut data, cmp, mergesort);
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn test_generic_elements() {
+        let words: Vec<String> = "the quick brown fox jumps over the lazy dog"
+            .split(' ')
+            .map(String::from)
+            .collect();
+        check_generic(&words, |a, b| a.cmp(b));
+
+        let floats = [3.5, -0.0, f64::INFINITY, 1e-9, -2.25, 0.0, f64::NEG_INFINITY, 3.5];
+        check_generic(&floats, f64::total_cmp);
+
+        let tuples = [(2, 'b'), (1, 'z'), (2, 'a'), (0, 'q'), (1, 'a')];
+        check_generic(&tuples, |a, b| a.cmp(b));
+
+        #[derive(Debug, Clone, Default, PartialEq)]
+        struct Student {
+            name: &'static str,
+            grade: u8,
+        }
+        let students = [
+            Student { name: "Ann", grade: 5 },
+            Student { name: "Bob", grade: 3 },
+            Student { name: "Eve", grade: 4 },
+        ];
+        check_generic(&students, |a, b| b.grade.cmp(&a.grade));
+    }
+
+    #[test]
+    fn test_names_are_unique() {
+        for (i, algorithm) in ALGORITHMS.iter().enumerate() {
+            for other in &ALGORITHMS[i + 1..] {
+                assert!(!other.matches(algorithm.name), "{} is ambiguous", algorithm.name);
+                for alias in algorithm.aliases {
+                    assert!(!other.matches(alias), "{} is ambiguous", alias);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_ignores_case() {
+        assert_eq!(find("HeapSort").map(|a| a.name), Some("heapsort"));
+        assert_eq!(find("radixsort").map(|a| a.name), Some("radix"));
+        assert!(find("unknown").is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use super::trace::Recorder;
+
+/// One step of a sorting network: comparators on disjoint wires, which
+/// could all run at the same time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    /// Size of the sorted blocks this layer's merge produces
+    pub block: usize,
+    /// Pairs `(i, j)` with `i < j`; each puts the smaller value on wire `i`
+    pub comparators: Vec<(usize, usize)>,
+}
+
+/// A sorting network: a fixed sequence of comparators that does not depend
+/// on the data, so every input of the same length is sorted the same way.
+///
+/// Both networks here are defined for powers of two. Other lengths are
+/// padded with virtual wires after the last element that hold +∞: since
+/// every comparator puts the smaller value on the lower wire, +∞ never moves
+/// and comparators touching padding are simply left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    /// Number of real wires, i.e. the array length
+    pub wires: usize,
+    pub layers: Vec<Layer>,
+    /// Phase name for the merges, followed by the block size
+    merge: &'static str,
+}
+
+impl Network {
+    /// Batcher's bitonic sorter. Each merge first compares a block's halves
+    /// mirrored (`i` with `block-1-i`), which turns two sorted halves into a
+    /// bitonic sequence without descending comparators, then half-cleans it.
+    pub fn bitonic(len: usize) -> Self {
+        let size = len.next_power_of_two();
+        let mut layers = Vec::new();
+        let mut block = 2;
+        while block <= size {
+            layers.push(Layer {
+                block,
+                comparators: (0..size)
+                    .map(|i| (i, i ^ (block - 1)))
+                    .filter(|&(i, j)| i < j)
+                    .collect(),
+            });
+            let mut distance = block / 4;
+            while distance > 0 {
+                layers.push(Layer {
+                    block,
+                    comparators: (0..size)
+                        .map(|i| (i, i ^ distance))
+                        .filter(|&(i, j)| i < j)
+                        .collect(),
+                });
+                distance /= 2;
+            }
+            block *= 2;
+        }
+        Self::pruned(len, layers, "bitonic merge")
+    }
+
+    /// Batcher's odd-even mergesort: merges sorted halves by merging their
+    /// even and odd subsequences, which needs fewer comparators than the
+    /// bitonic merge at the same depth.
+    pub fn odd_even_merge(len: usize) -> Self {
+        let size = len.next_power_of_two();
+        let mut layers = Vec::new();
+        let mut half = 1;
+        while half < size {
+            let mut distance = half;
+            while distance > 0 {
+                let mut comparators = Vec::new();
+                let mut start = distance % half;
+                while start + distance < size {
+                    for i in 0..distance.min(size - start - distance) {
+                        // Only pairs inside the same block of `2·half` are merged
+                        if (i + start) / (2 * half) == (i + start + distance) / (2 * half) {
+                            comparators.push((i + start, i + start + distance));
+                        }
+                    }
+                    start += 2 * distance;
+                }
+                layers.push(Layer { block: 2 * half, comparators });
+                distance /= 2;
+            }
+            half *= 2;
+        }
+        Self::pruned(len, layers, "odd-even merge")
+    }
+
+    /// Drops comparators on padding wires and the layers left empty
+    fn pruned(len: usize, layers: Vec<Layer>, merge: &'static str) -> Self {
+        let layers = layers
+            .into_iter()
+            .map(|layer| Layer {
+                block: layer.block,
+                comparators: layer.comparators.into_iter().filter(|&(_, j)| j < len).collect(),
+            })
+            .filter(|layer| !layer.comparators.is_empty())
+            .collect();
+        Self { wires: len, layers, merge }
+    }
+
+    /// Number of layers, the parallel running time of the network
+    pub fn depth(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn comparators(&self) -> usize {
+        self.layers.iter().map(|layer| layer.comparators.len()).sum()
+    }
+
+    /// Runs the network: one comparison per comparator, in layer order,
+    /// swapping when the pair is out of order. Each merge is a phase.
+    pub fn apply<T>(&self, rec: &mut Recorder<T>) {
+        let mut block = 0;
+        for layer in &self.layers {
+            if layer.block != block {
+                block = layer.block;
+                rec.phase(format!("{} {}", self.merge, block));
+            }
+            for &(i, j) in &layer.comparators {
+                if rec.compare(i, j).is_gt() {
+                    rec.swap(i, j);
+                }
+            }
+        }
+        rec.mark_sorted(0..self.wires);
+    }
+}
+
+/// Bitonic sort, see [`Network::bitonic`]
+pub fn bitonic_sort<T>(rec: &mut Recorder<T>) {
+    Network::bitonic(rec.len()).apply(rec);
+}
+
+/// Batcher's odd-even mergesort, see [`Network::odd_even_merge`]
+pub fn odd_even_merge_sort<T>(rec: &mut Recorder<T>) {
+    Network::odd_even_merge(rec.len()).apply(rec);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::Trace;
+
+    #[test]
+    fn test_sorting_networks() {
+        // 0-1 principle: a network that sorts every 0/1 input sorts everything,
+        // which also covers the padded lengths
+        for n in 1..=12usize {
+            for build in [Network::bitonic, Network::odd_even_merge] {
+                let network = build(n);
+                for bits in 0..1u32 << n {
+                    let mut data: Vec<u32> = (0..n).map(|i| bits >> i & 1).collect();
+                    Trace::count(&mut data, |rec| network.apply(rec));
+                    assert!(data.windows(2).all(|w| w[0] <= w[1]), "n={} {:?}", n, network);
+                }
+            }
+        }
+
+        // Known sizes for 8 and 16 wires
+        assert_eq!((Network::bitonic(8).depth(), Network::bitonic(8).comparators()), (6, 24));
+        assert_eq!((Network::bitonic(16).depth(), Network::bitonic(16).comparators()), (10, 80));
+        let odd_even = Network::odd_even_merge(16);
+        assert_eq!((odd_even.depth(), odd_even.comparators()), (10, 63));
+
+        // Comparators of a layer never share a wire
+        for layer in &Network::odd_even_merge(13).layers {
+            let mut wires: Vec<usize> = layer.comparators.iter().flat_map(|&(i, j)| [i, j]).collect();
+            let count = wires.len();
+            wires.sort_unstable();
+            wires.dedup();
+            assert_eq!(wires.len(), count);
+        }
+    }
+}
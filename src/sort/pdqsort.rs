@@ -0,0 +1,369 @@
+use super::trace::{PhaseTracker, Recorder};
+use super::xorshift::XorShift;
+use super::{heapsort, insertionsort};
+
+/// Slices this short are sorted with insertion sort
+const MAX_INSERTION: usize = 20;
+/// From this length the pivot is a median of medians of three (ninther)
+const SHORTEST_MEDIAN_OF_MEDIANS: usize = 50;
+/// Number of elements a partition block inspects before swapping
+const BLOCK: usize = 128;
+/// Sorting the pivot candidates takes at most this many swaps; reaching it
+/// means the slice is most likely descending
+const MAX_SWAPS: usize = 4 * 3;
+/// Out-of-order pairs `partial_insertion_sort` is willing to fix
+const MAX_STEPS: usize = 5;
+/// Below this length `partial_insertion_sort` only checks for sortedness
+const SHORTEST_SHIFTING: usize = 50;
+
+/// Pattern-defeating quicksort, as Rust's `sort_unstable` used it before 1.81.
+///
+/// Quicksort with block partitioning and a median-of-three (or ninther)
+/// pivot that also:
+/// - finishes already sorted or nearly sorted slices with a partial
+///   insertion sort when the previous partition moved nothing,
+/// - groups elements equal to the previous pivot in one pass,
+/// - shuffles a few elements after an unbalanced partition to break
+///   adversarial patterns,
+/// - falls back to heapsort after too many unbalanced partitions.
+///
+/// Each of these steps is recorded as a phase.
+pub fn pdqsort<T>(rec: &mut Recorder<T>) {
+    let len = rec.len();
+    let limit = (usize::BITS - len.leading_zeros()) as usize;
+    let mut sorter = PdqSort { phase: PhaseTracker::default() };
+    sorter.recurse(rec, 0, len, None, limit);
+}
+
+struct PdqSort {
+    phase: PhaseTracker,
+}
+
+impl PdqSort {
+    /// Sorts `lo..hi`; `pred` is the index of the pivot just before the
+    /// slice, if any, which is not greater than any element in it
+    fn recurse<T>(
+        &mut self,
+        rec: &mut Recorder<T>,
+        mut lo: usize,
+        mut hi: usize,
+        mut pred: Option<usize>,
+        mut limit: usize,
+    ) {
+        let mut was_balanced = true;
+        let mut was_partitioned = true;
+
+        loop {
+            let len = hi - lo;
+            if len <= MAX_INSERTION {
+                self.phase.switch(rec, "insertion sort");
+                insertionsort::sort_range(rec, lo, hi);
+                return;
+            }
+            if limit == 0 {
+                self.phase.switch(rec, "heapsort fallback");
+                heapsort::build_heap(rec, lo, hi);
+                heapsort::extract_max(rec, lo, hi);
+                return;
+            }
+            if !was_balanced {
+                self.phase.switch(rec, "break patterns");
+                break_patterns(rec, lo, hi);
+                limit -= 1;
+            }
+
+            let (pivot, likely_sorted) = self.choose_pivot(rec, lo, hi);
+
+            // The last partition was balanced and moved nothing: the slice
+            // may already be sorted, which is cheap to check and finish
+            if was_balanced && was_partitioned && likely_sorted {
+                self.phase.switch(rec, "partial insertion sort");
+                if partial_insertion_sort(rec, lo, hi) {
+                    rec.mark_sorted(lo..hi);
+                    return;
+                }
+            }
+
+            // A pivot equal to the previous one means many duplicates: put
+            // everything equal to it first, those are already in place
+            if let Some(pred) = pred
+                && !rec.compare(pred, pivot).is_lt()
+            {
+                self.phase.switch(rec, "partition equal");
+                let mid = partition_equal(rec, lo, hi, pivot);
+                rec.mark_sorted(lo..mid);
+                lo = mid;
+                continue;
+            }
+
+            self.phase.switch(rec, "block partition");
+            let (mid, partitioned) = partition(rec, lo, hi, pivot);
+            rec.mark_sorted(mid..mid + 1);
+            was_balanced = (mid - lo).min(hi - mid) >= len / 8;
+            was_partitioned = partitioned;
+
+            // Recurse into the shorter side and loop on the longer one to
+            // keep the stack depth logarithmic
+            if mid - lo < hi - mid {
+                self.recurse(rec, lo, mid, pred, limit);
+                pred = Some(mid);
+                lo = mid + 1;
+            } else {
+                self.recurse(rec, mid + 1, hi, Some(mid), limit);
+                hi = mid;
+            }
+        }
+    }
+
+    /// Picks the median of three (or of three medians of three) candidates
+    /// by comparing them without moving anything. Returns the pivot index and
+    /// whether the candidates were already in order. Candidates in exactly
+    /// descending order mean the slice is probably descending, so it is
+    /// reversed first.
+    fn choose_pivot<T>(&mut self, rec: &mut Recorder<T>, lo: usize, hi: usize) -> (usize, bool) {
+        let len = hi - lo;
+        let mut a = lo + len / 4;
+        let mut b = lo + len / 4 * 2;
+        let mut c = lo + len / 4 * 3;
+        let mut swaps = 0;
+
+        let mut sort2 = |rec: &mut Recorder<T>, a: &mut usize, b: &mut usize| {
+            if rec.compare(*b, *a).is_lt() {
+                std::mem::swap(a, b);
+                swaps += 1;
+            }
+        };
+        let mut sort3 = |rec: &mut Recorder<T>, a: &mut usize, b: &mut usize, c: &mut usize| {
+            sort2(rec, a, b);
+            sort2(rec, b, c);
+            sort2(rec, a, b);
+        };
+
+        if len >= 8 {
+            if len >= SHORTEST_MEDIAN_OF_MEDIANS {
+                for middle in [&mut a, &mut b, &mut c] {
+                    let (mut left, mut right) = (*middle - 1, *middle + 1);
+                    sort3(rec, &mut left, middle, &mut right);
+                }
+            }
+            sort3(rec, &mut a, &mut b, &mut c);
+        }
+
+        if swaps < MAX_SWAPS {
+            (b, swaps == 0)
+        } else {
+            self.phase.switch(rec, "reverse");
+            for i in 0..len / 2 {
+                rec.swap(lo + i, hi - 1 - i);
+            }
+            (hi - 1 - (b - lo), true)
+        }
+    }
+}
+
+/// Partitions `lo..hi` around the element at `pivot` and returns its final
+/// position, plus whether the slice was already partitioned.
+///
+/// The pivot is moved to `lo`. After skipping the prefix and suffix that are
+/// already on the right side, the rest is processed in blocks: a block from
+/// each end is scanned first, remembering the offsets of misplaced elements,
+/// and only then are the misplaced pairs swapped.
+fn partition<T>(rec: &mut Recorder<T>, lo: usize, hi: usize, pivot: usize) -> (usize, bool) {
+    rec.swap(lo, pivot);
+    rec.pivot(lo);
+    let mut l = lo + 1;
+    let mut r = hi;
+    while l < r && rec.compare(l, lo).is_lt() {
+        l += 1;
+    }
+    while l < r && !rec.compare(r - 1, lo).is_lt() {
+        r -= 1;
+    }
+    let was_partitioned = l >= r;
+
+    let mid = partition_in_blocks(rec, l, r, lo);
+    rec.swap(lo, mid - 1);
+    (mid - 1, was_partitioned)
+}
+
+/// Block partition of `l..r` around the pivot at `pivot`; returns the index
+/// of the first element not less than the pivot
+fn partition_in_blocks<T>(rec: &mut Recorder<T>, mut l: usize, mut r: usize, pivot: usize) -> usize {
+    let mut block_l = BLOCK;
+    let mut block_r = BLOCK;
+    // Offsets of elements in the left block that belong on the right, and
+    // of elements in the right block (counted from its end) that belong left
+    let mut offsets_l: Vec<usize> = Vec::with_capacity(BLOCK);
+    let mut offsets_r: Vec<usize> = Vec::with_capacity(BLOCK);
+    let (mut start_l, mut start_r) = (0, 0);
+
+    loop {
+        let is_done = r - l <= 2 * BLOCK;
+        if is_done {
+            // The last round: shrink the blocks so they exactly cover the rest
+            let mut rem = r - l;
+            if start_l < offsets_l.len() || start_r < offsets_r.len() {
+                rem -= BLOCK;
+            }
+            if start_l < offsets_l.len() {
+                block_r = rem;
+            } else if start_r < offsets_r.len() {
+                block_l = rem;
+            } else {
+                block_l = rem / 2;
+                block_r = rem - block_l;
+            }
+        }
+
+        if start_l == offsets_l.len() {
+            offsets_l.clear();
+            start_l = 0;
+            for i in 0..block_l {
+                if !rec.compare(l + i, pivot).is_lt() {
+                    offsets_l.push(i);
+                }
+            }
+        }
+        if start_r == offsets_r.len() {
+            offsets_r.clear();
+            start_r = 0;
+            for i in 0..block_r {
+                if rec.compare(r - 1 - i, pivot).is_lt() {
+                    offsets_r.push(i);
+                }
+            }
+        }
+
+        let count = (offsets_l.len() - start_l).min(offsets_r.len() - start_r);
+        for k in 0..count {
+            rec.swap(l + offsets_l[start_l + k], r - 1 - offsets_r[start_r + k]);
+        }
+        start_l += count;
+        start_r += count;
+
+        if start_l == offsets_l.len() {
+            l += block_l;
+        }
+        if start_r == offsets_r.len() {
+            r -= block_r;
+        }
+        if is_done {
+            break;
+        }
+    }
+
+    // One block may still hold misplaced elements: move them to the border
+    if start_l < offsets_l.len() {
+        while start_l < offsets_l.len() {
+            let offset = offsets_l.pop().unwrap_or_default();
+            rec.swap(l + offset, r - 1);
+            r -= 1;
+        }
+        r
+    } else {
+        while start_r < offsets_r.len() {
+            let offset = offsets_r.pop().unwrap_or_default();
+            rec.swap(l, r - 1 - offset);
+            l += 1;
+        }
+        l
+    }
+}
+
+/// Moves every element equal to the pivot at `pivot` to the front of
+/// `lo..hi`, assuming none is smaller; returns the end of that run
+fn partition_equal<T>(rec: &mut Recorder<T>, lo: usize, hi: usize, pivot: usize) -> usize {
+    rec.swap(lo, pivot);
+    rec.pivot(lo);
+    let mut l = lo + 1;
+    let mut r = hi;
+    loop {
+        while l < r && !rec.compare(lo, l).is_lt() {
+            l += 1;
+        }
+        while l < r && rec.compare(lo, r - 1).is_lt() {
+            r -= 1;
+        }
+        if l >= r {
+            break;
+        }
+        r -= 1;
+        rec.swap(l, r);
+        l += 1;
+    }
+    l
+}
+
+/// Fixes up to `MAX_STEPS` out-of-order pairs by shifting them into place;
+/// returns `true` if `lo..hi` ends up sorted
+fn partial_insertion_sort<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) -> bool {
+    let mut i = lo + 1;
+    for _ in 0..MAX_STEPS {
+        while i < hi && !rec.compare(i, i - 1).is_lt() {
+            i += 1;
+        }
+        if i == hi {
+            return true;
+        }
+        // Short slices are cheaper to partition than to shift
+        if hi - lo < SHORTEST_SHIFTING {
+            return false;
+        }
+        rec.swap(i - 1, i);
+        // The smaller element goes left, the larger one right
+        let mut j = i - 1;
+        while j > lo && rec.compare(j, j - 1).is_lt() {
+            rec.swap(j, j - 1);
+            j -= 1;
+        }
+        let mut j = i;
+        while j + 1 < hi && rec.compare(j + 1, j).is_lt() {
+            rec.swap(j, j + 1);
+            j += 1;
+        }
+    }
+    false
+}
+
+/// Swaps three elements around the middle with pseudo-random positions
+/// (seeded by the length, so runs are reproducible)
+fn break_patterns<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) {
+    let len = hi - lo;
+    if len < 8 {
+        return;
+    }
+    let mut random = XorShift::new(len as u64);
+    let modulus = len.next_power_of_two();
+    let pos = len / 4 * 2;
+    for i in 0..3 {
+        let mut other = random.next() & (modulus - 1);
+        if other >= len {
+            other -= len;
+        }
+        rec.swap(lo + pos - 1 + i, lo + other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{Trace, shaped_inputs};
+    use crate::utils::{Distribution, is_sorted};
+
+    #[test]
+    fn test_pdqsort_patterns() {
+        // Sorted and reversed input are detected without partitioning everything
+        for (label, mut data) in shaped_inputs(&[Distribution::Sorted, Distribution::Reversed], &[1000], (0, 100_000)) {
+            let trace = Trace::record(&mut data, pdqsort);
+            assert!(is_sorted(&data), "{}", label);
+            assert!(trace.counters.compares < 3 * 1000, "{}: {} compares", label, trace.counters.compares);
+        }
+        let shapes = [Distribution::Uniform, Distribution::FewUnique(Some(4)), Distribution::OrganPipe, Distribution::Sawtooth(None)];
+        for (label, mut data) in shaped_inputs(&shapes, &[0, 1, 21, 300, 5000], (-500, 500)) {
+            let mut expected = data.clone();
+            expected.sort_unstable();
+            Trace::record(&mut data, pdqsort);
+            assert_eq!(data, expected, "{}", label);
+        }
+    }
+}
@@ -1,31 +1,321 @@
-pub fn quicksort<F>(arr: &mut [i32], visualize: &F)
-where
-    F: Fn(&[i32], &[usize]),
-{
-    if arr.len() <= 1 {
-        return;
-    }
-    let pivot_idx = partition(arr, visualize);
-    let _ = visualize(arr, &[pivot_idx]);
-    quicksort(&mut arr[..pivot_idx], visualize);
-    quicksort(&mut arr[pivot_idx + 1..], visualize);
-}
-
-fn partition<F>(arr: &mut [i32], visualize: &F) -> usize
-where
-    F: Fn(&[i32], &[usize]),
-{
-    let len = arr.len();
-    let pivot = arr[len - 1];
-    let mut i = 0;
-    for j in 0..len - 1 {
-        if arr[j] <= pivot {
-            arr.swap(i, j);
-            let _ = visualize(arr, &[i, j]);
-            i += 1;
-        }
-    }
-    arr.swap(i, len - 1);
-    let _ = visualize(arr, &[i, len - 1]);
-    i
-}
+use super::SortOptions;
+use super::trace::Recorder;
+use super::xorshift::XorShift;
+
+/// How a slice is split around its pivot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Partition {
+    /// One index sweeps left to right, growing the `< pivot` prefix
+    Lomuto,
+    /// Two indices meet in the middle, swapping pairs that are on the wrong side
+    Hoare,
+    /// Dijkstra's Dutch national flag: `<`, `==` and `>` regions in one pass
+    ThreeWay,
+    /// Yaroslavskiy's dual-pivot scheme: `< p1`, `p1..=p2` and `> p2`
+    DualPivot,
+}
+
+/// Which element becomes the pivot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PivotRule {
+    First,
+    Last,
+    Middle,
+    /// Pseudo-random, from the seed in the options (the session seed in
+    /// the app), or from the length without one
+    Random,
+    /// Median of the first, middle and last elements
+    MedianOfThree,
+    /// Tukey's ninther: median of three medians of three
+    Ninther,
+}
+
+impl Partition {
+    fn from_option(value: Option<&str>) -> Self {
+        match value {
+            Some("hoare") => Partition::Hoare,
+            Some("threeway") => Partition::ThreeWay,
+            Some("dualpivot") => Partition::DualPivot,
+            _ => Partition::Lomuto,
+        }
+    }
+}
+
+impl PivotRule {
+    fn from_option(value: Option<&str>) -> Self {
+        match value {
+            Some("first") => PivotRule::First,
+            Some("middle") => PivotRule::Middle,
+            Some("random") => PivotRule::Random,
+            Some("median3") => PivotRule::MedianOfThree,
+            Some("ninther") => PivotRule::Ninther,
+            _ => PivotRule::Last,
+        }
+    }
+}
+
+/// Quicksort with a Lomuto partition around the last element
+pub fn quicksort<T>(rec: &mut Recorder<T>) {
+    quicksort_with(rec, Partition::Lomuto, PivotRule::Last, None);
+}
+
+/// Registry entry point: reads `partition`, `pivot` and the seed from the options
+pub fn run<T>(rec: &mut Recorder<T>, options: &SortOptions) {
+    quicksort_with(
+        rec,
+        Partition::from_option(options.get("partition")),
+        PivotRule::from_option(options.get("pivot")),
+        options.seed(),
+    );
+}
+
+/// Quicksort with the given partition scheme and pivot rule; `seed` is
+/// only used by `PivotRule::Random`.
+///
+/// With the dual-pivot scheme the rule picks the first pivot from the left
+/// half of the slice and the second one from the right half.
+pub fn quicksort_with<T>(rec: &mut Recorder<T>, partition: Partition, pivot: PivotRule, seed: Option<u64>) {
+    let len = rec.len();
+    let random = XorShift::new(seed.unwrap_or(len as u64));
+    let mut sorter = QuickSort { partition, pivot, random };
+    sorter.sort_range(rec, 0, len);
+}
+
+struct QuickSort {
+    partition: Partition,
+    pivot: PivotRule,
+    /// Generator for `PivotRule::Random`
+    random: XorShift,
+}
+
+impl QuickSort {
+    fn sort_range<T>(&mut self, rec: &mut Recorder<T>, lo: usize, hi: usize) {
+        if hi - lo <= 1 {
+            rec.mark_sorted(lo..hi);
+            return;
+        }
+        match self.partition {
+            Partition::Lomuto | Partition::Hoare => {
+                let pivot = self.choose(rec, lo, hi);
+                let mid = if self.partition == Partition::Lomuto {
+                    rec.swap(pivot, hi - 1);
+                    partition(rec, lo, hi)
+                } else {
+                    rec.swap(pivot, lo);
+                    hoare(rec, lo, hi)
+                };
+                rec.mark_sorted(mid..mid + 1);
+                self.sort_range(rec, lo, mid);
+                self.sort_range(rec, mid + 1, hi);
+            }
+            Partition::ThreeWay => {
+                let pivot = self.choose(rec, lo, hi);
+                rec.swap(pivot, lo);
+                let (lt, gt) = three_way(rec, lo, hi);
+                rec.mark_sorted(lt..gt);
+                self.sort_range(rec, lo, lt);
+                self.sort_range(rec, gt, hi);
+            }
+            Partition::DualPivot => {
+                let mid = lo + (hi - lo) / 2;
+                let first = self.choose(rec, lo, mid);
+                let second = self.choose(rec, mid, hi);
+                // `first` lies in the left half and `second` in the right one,
+                // so moving one cannot displace the other
+                rec.swap(first, lo);
+                rec.swap(second, hi - 1);
+                let (lt, gt) = dual_pivot(rec, lo, hi);
+                rec.mark_sorted(lt..lt + 1);
+                rec.mark_sorted(gt..gt + 1);
+                self.sort_range(rec, lo, lt);
+                self.sort_range(rec, lt + 1, gt);
+                self.sort_range(rec, gt + 1, hi);
+            }
+        }
+    }
+
+    /// Index of the pivot for `lo..hi` under the current rule; elements are
+    /// only compared here, not moved
+    fn choose<T>(&mut self, rec: &mut Recorder<T>, lo: usize, hi: usize) -> usize {
+        let len = hi - lo;
+        let mid = lo + len / 2;
+        match self.pivot {
+            PivotRule::First => lo,
+            PivotRule::Last => hi - 1,
+            PivotRule::Middle => mid,
+            PivotRule::Random => lo + self.random.next() % len,
+            PivotRule::MedianOfThree => median(rec, lo, mid, hi - 1),
+            PivotRule::Ninther if len >= 9 => {
+                let step = len / 8;
+                let a = median(rec, lo, lo + step, lo + 2 * step);
+                let b = median(rec, mid - step, mid, mid + step);
+                let c = median(rec, hi - 1 - 2 * step, hi - 1 - step, hi - 1);
+                median(rec, a, b, c)
+            }
+            PivotRule::Ninther => median(rec, lo, mid, hi - 1),
+        }
+    }
+}
+
+/// Index of the median of the elements at `a`, `b` and `c`
+fn median<T>(rec: &mut Recorder<T>, a: usize, b: usize, c: usize) -> usize {
+    let (a, b) = if rec.compare(b, a).is_lt() { (b, a) } else { (a, b) };
+    // `a` is now the smaller of the two, so the median is `b`, `c` or `a`
+    if rec.compare(c, b).is_ge() {
+        b
+    } else if rec.compare(c, a).is_ge() {
+        c
+    } else {
+        a
+    }
+}
+
+/// Lomuto partition of `lo..hi` around the last element; returns the
+/// pivot's final position
+pub(super) fn partition<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) -> usize {
+    let last = hi - 1;
+    rec.pivot(last);
+    let mut i = lo;
+    for j in lo..last {
+        if rec.compare(j, last).is_le() {
+            rec.swap(i, j);
+            i += 1;
+        }
+    }
+    rec.swap(i, last);
+    i
+}
+
+/// Hoare partition of `lo..hi` around the first element; returns the
+/// pivot's final position. Both scans stop on elements equal to the pivot,
+/// so duplicates are spread over both sides.
+fn hoare<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) -> usize {
+    rec.pivot(lo);
+    let mut i = lo;
+    let mut j = hi;
+    loop {
+        i += 1;
+        while i < hi - 1 && rec.compare(i, lo).is_lt() {
+            i += 1;
+        }
+        j -= 1;
+        // The pivot itself stops this scan
+        while rec.compare(j, lo).is_gt() {
+            j -= 1;
+        }
+        if i >= j {
+            break;
+        }
+        rec.swap(i, j);
+    }
+    rec.swap(lo, j);
+    j
+}
+
+/// Three-way partition of `lo..hi` around the first element; returns the
+/// range of elements equal to the pivot
+fn three_way<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) -> (usize, usize) {
+    rec.pivot(lo);
+    // `lt..i` holds elements equal to the pivot, so `lt` always holds one
+    let mut lt = lo;
+    let mut i = lo + 1;
+    let mut gt = hi;
+    while i < gt {
+        match rec.compare(i, lt) {
+            std::cmp::Ordering::Less => {
+                rec.swap(lt, i);
+                lt += 1;
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                gt -= 1;
+                rec.swap(i, gt);
+            }
+            std::cmp::Ordering::Equal => i += 1,
+        }
+    }
+    (lt, gt)
+}
+
+/// Dual-pivot partition of `lo..hi` around the first and last elements;
+/// returns the final positions of the smaller and the larger pivot
+fn dual_pivot<T>(rec: &mut Recorder<T>, lo: usize, hi: usize) -> (usize, usize) {
+    let last = hi - 1;
+    if rec.compare(last, lo).is_lt() {
+        rec.swap(lo, last);
+    }
+    rec.pivot(lo);
+    rec.pivot(last);
+    // `lo+1..lt` is below the first pivot, `gt..last` above the second
+    let mut lt = lo + 1;
+    let mut gt = last;
+    let mut k = lo + 1;
+    while k < gt {
+        if rec.compare(k, lo).is_lt() {
+            rec.swap(k, lt);
+            lt += 1;
+        } else if rec.compare(k, last).is_gt() {
+            while k < gt - 1 && rec.compare(gt - 1, last).is_gt() {
+                gt -= 1;
+            }
+            gt -= 1;
+            rec.swap(k, gt);
+            if rec.compare(k, lo).is_lt() {
+                rec.swap(k, lt);
+                lt += 1;
+            }
+        }
+        k += 1;
+    }
+    rec.swap(lo, lt - 1);
+    rec.swap(last, gt);
+    (lt - 1, gt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{AlgorithmSpec, Trace, find, shaped_inputs};
+    use crate::utils::Distribution;
+
+    #[test]
+    fn test_quicksort_variants() {
+        let quick = find("quick").unwrap();
+        let shapes = [Distribution::Uniform, Distribution::FewUnique(Some(3)), Distribution::Sorted, Distribution::Reversed];
+        for (label, input) in shaped_inputs(&shapes, &[0, 1, 2, 3, 9, 200], (-50, 50)) {
+            let mut expected = input.clone();
+            expected.sort_unstable();
+            for partition in ["lomuto", "hoare", "threeway", "dualpivot"] {
+                for pivot in ["last", "first", "middle", "random", "median3", "ninther"] {
+                    let mut spec = AlgorithmSpec::new(quick);
+                    spec.set_option(partition).unwrap();
+                    spec.set_option(&format!("pivot={}", pivot)).unwrap();
+                    let mut data = input.clone();
+                    Trace::record(&mut data, |rec| spec.run(rec));
+                    assert_eq!(data, expected, "{} {} {}", partition, pivot, label);
+                }
+            }
+        }
+
+        // A median-of-three pivot keeps sorted input at O(n log n) comparisons
+        let mut sorted: Vec<i32> = (0..1000).collect();
+        let naive = Trace::count(&mut sorted.clone(), quicksort).compares;
+        let mut spec = AlgorithmSpec::new(quick);
+        spec.set_option("pivot=median3").unwrap();
+        let median = Trace::count(&mut sorted, |rec| spec.run(rec)).compares;
+        assert!(median * 10 < naive, "{} vs {}", median, naive);
+    }
+
+    #[test]
+    fn test_random_pivot_follows_the_seed() {
+        let input: Vec<i32> = (0..200).rev().collect();
+        let run = |seed| {
+            let mut spec = AlgorithmSpec::new(find("quick").unwrap());
+            spec.set_option("pivot=random").unwrap();
+            spec.set_seed(seed);
+            Trace::record(&mut input.clone(), |rec| spec.run(rec)).events
+        };
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42), run(43));
+    }
+}
@@ -1,43 +1,175 @@
-pub fn radixsort<F>(arr: &mut [i32], visualize: &F)
-where
-    F: Fn(&[i32], &[usize]),
-{
-    let max = match arr.iter().max() {
-        Some(&m) => m,
-        None => return,
-    };
-    let mut exp = 1;
-    while exp <= max {
-        counting_sort(arr, exp, visualize);
-        exp *= 10;
-    }
-}
-
-fn counting_sort<F>(arr: &mut [i32], exp: i32, visualize: &F)
-where
-    F: Fn(&[i32], &[usize]),
-{
-    let n = arr.len();
-    let mut output = vec![0; n];
-    let mut count = vec![0; 10];
-
-    for num in arr.iter_mut() {
-        let digit = ((*num / exp) % 10) as usize;
-        count[digit] += 1;
-    }
-
-    for i in 1..10 {
-        count[i] += count[i - 1];
-    }
-
-    for num in arr.iter_mut().rev() {
-        let digit = ((*num / exp) % 10) as usize;
-        output[count[digit] - 1] = *num;
-        count[digit] -= 1;
-    }
-
-    for i in 0..n {
-        arr[i] = output[i];
-    }
-    visualize(arr, &(0..arr.len()).collect::<Vec<_>>());
-}
+use super::trace::Recorder;
+use super::{IntKey, SortOptions};
+
+/// Registry entry point: reads `variant` and `base` from the options
+pub fn run<T: Clone + IntKey>(rec: &mut Recorder<T>, options: &SortOptions) {
+    if rec.is_empty() {
+        return;
+    }
+    let base = options.get("base").and_then(|b| b.parse().ok()).unwrap_or(10);
+    match options.get("variant") {
+        Some("msd") => radix_msd(rec, base),
+        _ => radix_lsd(rec, base),
+    }
+}
+
+/// Least-significant-digit radix sort.
+///
+/// Keys are shifted by the minimum, so the whole `i32` range
+/// (negatives included) maps onto `0..=u32::MAX` without changing order.
+pub fn radix_lsd<T: Clone + IntKey>(rec: &mut Recorder<T>, base: u32) {
+    let n = rec.len();
+    let Some(min) = (0..n).map(|i| rec.get(i).key()).min() else {
+        return;
+    };
+    let keys = Keys::new(rec, min, base);
+
+    let mut output: Vec<Option<T>> = vec![None; n];
+    rec.aux_alloc(n + base as usize);
+    for digit in 0..keys.digits {
+        let mut count = vec![0usize; base as usize];
+        for i in 0..n {
+            count[keys.digit(rec.get(i).key(), digit)] += 1;
+        }
+        rec.phase(keys.phase_name("LSD", digit, &count));
+
+        // Bucket starts, then a stable scatter into the output array
+        let mut start = 0;
+        for c in count.iter_mut() {
+            (*c, start) = (start, start + *c);
+        }
+        for i in 0..n {
+            let num = rec.get(i);
+            let bucket = keys.digit(num.key(), digit);
+            output[count[bucket]] = Some(num.clone());
+            rec.aux_write(count[bucket], num);
+            count[bucket] += 1;
+        }
+
+        for (i, num) in output.iter_mut().enumerate() {
+            if let Some(num) = num.take() {
+                rec.write(i, num);
+            }
+        }
+    }
+    rec.aux_free(n + base as usize);
+    rec.mark_sorted(0..n);
+}
+
+/// Most-significant-digit radix sort, recursing into each bucket
+pub fn radix_msd<T: Clone + IntKey>(rec: &mut Recorder<T>, base: u32) {
+    let n = rec.len();
+    let Some(min) = (0..n).map(|i| rec.get(i).key()).min() else {
+        return;
+    };
+    let keys = Keys::new(rec, min, base);
+    let mut output: Vec<Option<T>> = vec![None; n];
+    rec.aux_alloc(n);
+    msd_bucket(rec, &keys, &mut output, 0, n, keys.digits);
+    rec.aux_free(n);
+}
+
+fn msd_bucket<T: Clone + IntKey>(
+    rec: &mut Recorder<T>,
+    keys: &Keys,
+    output: &mut [Option<T>],
+    lo: usize,
+    hi: usize,
+    digits_left: u32,
+) {
+    if hi - lo <= 1 || digits_left == 0 {
+        rec.mark_sorted(lo..hi);
+        return;
+    }
+    let digit = digits_left - 1;
+
+    // Each level keeps its counts and bucket bounds while recursing
+    let mut count = vec![0usize; keys.base as usize];
+    let level_aux = 3 * count.len() + 2;
+    rec.aux_alloc(level_aux);
+    for i in lo..hi {
+        count[keys.digit(rec.get(i).key(), digit)] += 1;
+    }
+    rec.phase(keys.phase_name("MSD", digit, &count));
+
+    // Buckets are laid out in the auxiliary array at the same positions
+    // they will occupy in the main array.
+    let mut bounds = Vec::with_capacity(count.len() + 1);
+    let mut start = lo;
+    for &c in &count {
+        bounds.push(start);
+        start += c;
+    }
+    bounds.push(hi);
+
+    let mut next = bounds.clone();
+    for i in lo..hi {
+        let num = rec.get(i);
+        let bucket = keys.digit(num.key(), digit);
+        output[next[bucket]] = Some(num.clone());
+        rec.aux_write(next[bucket], num);
+        next[bucket] += 1;
+    }
+    for (i, num) in output.iter_mut().enumerate().take(hi).skip(lo) {
+        if let Some(num) = num.take() {
+            rec.write(i, num);
+        }
+    }
+
+    for bucket in bounds.windows(2) {
+        msd_bucket(rec, keys, output, bucket[0], bucket[1], digit);
+    }
+    rec.aux_free(level_aux);
+}
+
+/// Maps values to unsigned keys and splits them into digits of `base`
+struct Keys {
+    min: i32,
+    base: u32,
+    digits: u32,
+}
+
+impl Keys {
+    fn new<T: Clone + IntKey>(rec: &Recorder<T>, min: i32, base: u32) -> Self {
+        let mut keys = Self { min, base, digits: 1 };
+        let max_key = (0..rec.len()).map(|i| keys.key(rec.get(i).key())).max().unwrap_or(0);
+        let mut rest = max_key / u64::from(base);
+        while rest > 0 {
+            keys.digits += 1;
+            rest /= u64::from(base);
+        }
+        keys
+    }
+
+    fn key(&self, value: i32) -> u64 {
+        (i64::from(value) - i64::from(self.min)) as u64
+    }
+
+    fn digit(&self, value: i32, digit: u32) -> usize {
+        let base = u64::from(self.base);
+        ((self.key(value) / base.pow(digit)) % base) as usize
+    }
+
+    /// Phase label with the bucket sizes for this digit; large bases only
+    /// list the non-empty buckets.
+    fn phase_name(&self, variant: &str, digit: u32, count: &[usize]) -> String {
+        let buckets: Vec<String> = if self.base <= 16 {
+            count.iter().map(|c| c.to_string()).collect()
+        } else {
+            count
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| **c > 0)
+                .map(|(b, c)| format!("{}:{}", b, c))
+                .collect()
+        };
+        format!(
+            "{} digit {}/{} base {} buckets [{}]",
+            variant,
+            digit + 1,
+            self.digits,
+            self.base,
+            buckets.join(" ")
+        )
+    }
+}
@@ -1,13 +1,17 @@
-fn selection_sort(arr: &mut [i32]) {
-    let len = arr.len();
-    for i in 0..len {
-        let mut min_index = i;
-        for j in (i + 1)..len {
-            if arr[j] < arr[min_index] {
-                min_index = j;
-            }
-        }
-        arr.swap(i, min_index);
-    }
-}
-
+use super::trace::Recorder;
+
+pub fn selection_sort<T>(rec: &mut Recorder<T>) {
+    let len = rec.len();
+    for i in 0..len {
+        let mut min_index = i;
+        for j in (i + 1)..len {
+            if rec.compare(j, min_index).is_lt() {
+                min_index = j;
+            }
+        }
+        if min_index != i {
+            rec.swap(i, min_index);
+        }
+        rec.mark_sorted(i..i + 1);
+    }
+}
@@ -1,19 +1,24 @@
-
-fn shell_sort(arr: &mut [i32]) {
-    let n = arr.len();
-    let mut gap = n / 2;
-
-    while gap > 0 {
-        for i in gap..n {
-            let temp = arr[i];
-            let mut j = i;
-
-            while j >= gap && arr[j - gap] > temp {
-                arr[j] = arr[j - gap];
-                j -= gap;
-            }
-            arr[j] = temp;
-        }
-        gap /= 2;
-    }
-}
+use super::trace::Recorder;
+
+pub fn shell_sort<T: Clone>(rec: &mut Recorder<T>) {
+    let n = rec.len();
+    let mut gap = n / 2;
+
+    while gap > 0 {
+        rec.phase(format!("gap {}", gap));
+        for i in gap..n {
+            let temp = rec.get(i);
+            let mut j = i;
+
+            while j >= gap && rec.compare_value(j - gap, &temp, j).is_gt() {
+                rec.write(j, rec.get(j - gap));
+                j -= gap;
+            }
+            if j != i {
+                rec.write(j, temp);
+            }
+        }
+        gap /= 2;
+    }
+    rec.mark_sorted(0..n);
+}
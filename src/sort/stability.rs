@@ -0,0 +1,126 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use super::IntKey;
+
+/// A key together with the position it had before sorting.
+///
+/// Records are ordered by `key` only, so equal keys can end up in either
+/// order; `index` shows which one came first. `tag` numbers the records that
+/// share a key (0 for the first occurrence) and is displayed as a letter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tagged {
+    pub key: i32,
+    pub index: usize,
+    pub tag: usize,
+}
+
+impl Tagged {
+    /// Tags every key with its position and its occurrence among equal keys
+    pub fn from_keys(keys: &[i32]) -> Vec<Tagged> {
+        let mut seen = std::collections::HashMap::new();
+        keys.iter()
+            .enumerate()
+            .map(|(index, &key)| {
+                let count = seen.entry(key).or_insert(0);
+                *count += 1;
+                Tagged { key, index, tag: *count - 1 }
+            })
+            .collect()
+    }
+
+    /// The order sorts use for records: by key, ignoring the tag
+    pub fn by_key(a: &Tagged, b: &Tagged) -> Ordering {
+        a.key.cmp(&b.key)
+    }
+
+    /// `a`, `b`, ..., `z`, then `a1`, `b1`, ...
+    pub fn tag_label(tag: usize) -> String {
+        let letter = (b'a' + (tag % 26) as u8) as char;
+        match tag / 26 {
+            0 => letter.to_string(),
+            round => format!("{}{}", letter, round),
+        }
+    }
+}
+
+impl IntKey for Tagged {
+    fn key(&self) -> i32 {
+        self.key
+    }
+}
+
+impl fmt::Display for Tagged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.key, Self::tag_label(self.tag))
+    }
+}
+
+/// Outcome of checking a sorted array of records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stability {
+    Stable,
+    /// The first pair of equal keys found in swapped order, with the
+    /// position of `first` in the sorted array
+    Unstable {
+        position: usize,
+        first: Tagged,
+        second: Tagged,
+    },
+}
+
+/// Checks that records with equal keys kept their original order.
+///
+/// `sorted` must already be sorted by key, so equal keys are adjacent.
+pub fn check_stability(sorted: &[Tagged]) -> Stability {
+    sorted
+        .windows(2)
+        .position(|pair| pair[0].key == pair[1].key && pair[0].index > pair[1].index)
+        .map_or(Stability::Stable, |position| Stability::Unstable {
+            position,
+            first: sorted[position],
+            second: sorted[position + 1],
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::{ALGORITHMS, AlgorithmSpec, Trace};
+    use crate::utils::generate_random_list;
+
+    #[test]
+    fn test_tags_number_equal_keys() {
+        let records = Tagged::from_keys(&[5, 3, 5]);
+        let labels: Vec<String> = records.iter().map(|r| r.to_string()).collect();
+        assert_eq!(labels, vec!["5a", "3a", "5b"]);
+        assert_eq!(Tagged::tag_label(27), "b1");
+    }
+
+    #[test]
+    fn test_reports_first_violation() {
+        let mut records = Tagged::from_keys(&[2, 1, 2, 1]);
+        records.sort_by(Tagged::by_key);
+        assert_eq!(check_stability(&records), Stability::Stable);
+
+        records.swap(0, 1);
+        match check_stability(&records) {
+            Stability::Unstable { position, first, second } => {
+                assert_eq!(position, 0);
+                assert_eq!((first.to_string(), second.to_string()), ("1b".into(), "1a".into()));
+            }
+            Stability::Stable => panic!("swapped records reported as stable"),
+        }
+    }
+
+    #[test]
+    fn test_stable_algorithms_keep_equal_keys_in_order() {
+        let keys = generate_random_list(Some(200), 0, 9, false);
+        for algorithm in ALGORITHMS.iter().filter(|a| a.stable) {
+            let mut records = Tagged::from_keys(&keys);
+            let spec = AlgorithmSpec::new(algorithm);
+            Trace::record_by(&mut records, Tagged::by_key, |rec| spec.run_tagged(rec));
+            assert_eq!(check_stability(&records), Stability::Stable, "{}", algorithm.name);
+        }
+    }
+}
@@ -1,7 +1,595 @@
-pub fn timsort<F>(arr: &mut [i32], visualize: &F)
-where
-    F: Fn(&[i32], &[usize]),
-{
-    arr.sort_unstable();
-    visualize(arr, &(0..arr.len()).collect::<Vec<_>>());
-}
+use std::cmp::Ordering;
+
+use super::trace::Recorder;
+
+/// Arrays shorter than this are sorted with a single binary insertion pass
+const MIN_MERGE: usize = 32;
+
+/// Initial number of consecutive wins before a merge switches to galloping
+const MIN_GALLOP: usize = 7;
+
+/// TimSort: natural runs, binary insertion up to `minrun`, and merges from
+/// a run stack with galloping, following the CPython/Java implementation.
+pub fn timsort<T: Clone>(rec: &mut Recorder<T>) {
+    let n = rec.len();
+    if n < 2 {
+        rec.mark_sorted(0..n);
+        return;
+    }
+
+    if n < MIN_MERGE {
+        let run_len = count_run_and_make_ascending(rec, 0, n);
+        binary_sort(rec, 0, n, run_len);
+        rec.mark_sorted(0..n);
+        return;
+    }
+
+    let mut state = TimSort::new();
+    let min_run = min_run_length(n);
+    let mut lo = 0;
+    while lo < n {
+        let mut run_len = count_run_and_make_ascending(rec, lo, n);
+
+        // Extend a short natural run to min(min_run, remaining)
+        if run_len < min_run {
+            let force = min_run.min(n - lo);
+            binary_sort(rec, lo, lo + force, lo + run_len);
+            run_len = force;
+        }
+
+        state.runs.push(Run { base: lo, len: run_len });
+        state.merge_collapse(rec);
+        lo += run_len;
+    }
+    state.merge_force_collapse(rec);
+    rec.mark_sorted(0..n);
+}
+
+/// Smallest run length such that `n / minrun` is close to a power of two
+fn min_run_length(mut n: usize) -> usize {
+    let mut r = 0;
+    while n >= MIN_MERGE {
+        r |= n & 1;
+        n >>= 1;
+    }
+    n + r
+}
+
+/// Finds the run starting at `lo`, reversing it in place if it is strictly
+/// descending, and returns its length.
+fn count_run_and_make_ascending<T: Clone>(rec: &mut Recorder<T>, lo: usize, hi: usize) -> usize {
+    rec.phase("run detection");
+    let mut run_hi = lo + 1;
+    if run_hi == hi {
+        return 1;
+    }
+
+    if rec.compare(run_hi, lo).is_lt() {
+        run_hi += 1;
+        while run_hi < hi && rec.compare(run_hi, run_hi - 1).is_lt() {
+            run_hi += 1;
+        }
+        reverse_range(rec, lo, run_hi);
+    } else {
+        run_hi += 1;
+        while run_hi < hi && rec.compare(run_hi, run_hi - 1).is_ge() {
+            run_hi += 1;
+        }
+    }
+    run_hi - lo
+}
+
+fn reverse_range<T: Clone>(rec: &mut Recorder<T>, lo: usize, hi: usize) {
+    let (mut i, mut j) = (lo, hi - 1);
+    while i < j {
+        rec.swap(i, j);
+        i += 1;
+        j -= 1;
+    }
+}
+
+/// Binary insertion sort of `lo..hi`, where `lo..start` is already sorted
+fn binary_sort<T: Clone>(rec: &mut Recorder<T>, lo: usize, hi: usize, start: usize) {
+    rec.phase("binary insertion");
+    for start in start.max(lo + 1)..hi {
+        let pivot = rec.get(start);
+        rec.pivot(start);
+
+        // Rightmost position that keeps equal elements in order
+        let (mut left, mut right) = (lo, start);
+        while left < right {
+            let mid = left + (right - left) / 2;
+            if rec.compare_value(mid, &pivot, start).is_gt() {
+                right = mid;
+            } else {
+                left = mid + 1;
+            }
+        }
+
+        for k in (left + 1..=start).rev() {
+            rec.write(k, rec.get(k - 1));
+        }
+        if left != start {
+            rec.write(left, pivot);
+        }
+    }
+}
+
+/// Leftmost insertion point for a key among `len` sorted elements, searching
+/// outwards from `hint`. `key_cmp(k)` compares the key with element `k`.
+fn gallop_left<C>(len: usize, hint: usize, mut key_cmp: C) -> usize
+where
+    C: FnMut(usize) -> Ordering,
+{
+    let hint = hint as isize;
+    let mut last_ofs: isize = 0;
+    let mut ofs: isize = 1;
+
+    if key_cmp(hint as usize).is_gt() {
+        // Gallop right until element[hint + last_ofs] < key <= element[hint + ofs]
+        let max_ofs = len as isize - hint;
+        while ofs < max_ofs && key_cmp((hint + ofs) as usize).is_gt() {
+            last_ofs = ofs;
+            ofs = (ofs << 1) + 1;
+        }
+        ofs = ofs.min(max_ofs);
+        last_ofs += hint;
+        ofs += hint;
+    } else {
+        // Gallop left until element[hint - ofs] < key <= element[hint - last_ofs]
+        let max_ofs = hint + 1;
+        while ofs < max_ofs && key_cmp((hint - ofs) as usize).is_le() {
+            last_ofs = ofs;
+            ofs = (ofs << 1) + 1;
+        }
+        ofs = ofs.min(max_ofs);
+        (last_ofs, ofs) = (hint - ofs, hint - last_ofs);
+    }
+
+    last_ofs += 1;
+    while last_ofs < ofs {
+        let m = last_ofs + (ofs - last_ofs) / 2;
+        if key_cmp(m as usize).is_gt() {
+            last_ofs = m + 1;
+        } else {
+            ofs = m;
+        }
+    }
+    ofs as usize
+}
+
+/// Like [`gallop_left`], but returns the rightmost insertion point
+fn gallop_right<C>(len: usize, hint: usize, mut key_cmp: C) -> usize
+where
+    C: FnMut(usize) -> Ordering,
+{
+    let hint = hint as isize;
+    let mut last_ofs: isize = 0;
+    let mut ofs: isize = 1;
+
+    if key_cmp(hint as usize).is_lt() {
+        // Gallop left until element[hint - ofs] <= key < element[hint - last_ofs]
+        let max_ofs = hint + 1;
+        while ofs < max_ofs && key_cmp((hint - ofs) as usize).is_lt() {
+            last_ofs = ofs;
+            ofs = (ofs << 1) + 1;
+        }
+        ofs = ofs.min(max_ofs);
+        (last_ofs, ofs) = (hint - ofs, hint - last_ofs);
+    } else {
+        // Gallop right until element[hint + last_ofs] <= key < element[hint + ofs]
+        let max_ofs = len as isize - hint;
+        while ofs < max_ofs && key_cmp((hint + ofs) as usize).is_ge() {
+            last_ofs = ofs;
+            ofs = (ofs << 1) + 1;
+        }
+        ofs = ofs.min(max_ofs);
+        last_ofs += hint;
+        ofs += hint;
+    }
+
+    last_ofs += 1;
+    while last_ofs < ofs {
+        let m = last_ofs + (ofs - last_ofs) / 2;
+        if key_cmp(m as usize).is_lt() {
+            ofs = m;
+        } else {
+            last_ofs = m + 1;
+        }
+    }
+    ofs as usize
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    base: usize,
+    len: usize,
+}
+
+/// Merge state: the pending run stack, the adaptive gallop threshold and
+/// the temporary buffer shown as the auxiliary array.
+struct TimSort<T> {
+    runs: Vec<Run>,
+    min_gallop: usize,
+    tmp: Vec<T>,
+}
+
+impl<T: Clone> TimSort<T> {
+    fn new() -> Self {
+        Self {
+            runs: Vec::new(),
+            min_gallop: MIN_GALLOP,
+            tmp: Vec::new(),
+        }
+    }
+
+    /// Merges runs until the stack invariants hold again:
+    /// `len[n-2] > len[n-1] + len[n]` and `len[n-1] > len[n]`.
+    fn merge_collapse(&mut self, rec: &mut Recorder<T>) {
+        while self.runs.len() > 1 {
+            let mut n = self.runs.len() - 2;
+            let len = |i: usize| self.runs[i].len;
+            if (n > 0 && len(n - 1) <= len(n) + len(n + 1))
+                || (n > 1 && len(n - 2) <= len(n) + len(n - 1))
+            {
+                if len(n - 1) < len(n + 1) {
+                    n -= 1;
+                }
+            } else if len(n) > len(n + 1) {
+                break;
+            }
+            self.merge_at(rec, n);
+        }
+    }
+
+    fn merge_force_collapse(&mut self, rec: &mut Recorder<T>) {
+        while self.runs.len() > 1 {
+            let mut n = self.runs.len() - 2;
+            if n > 0 && self.runs[n - 1].len < self.runs[n + 1].len {
+                n -= 1;
+            }
+            self.merge_at(rec, n);
+        }
+    }
+
+    /// Merges the runs at stack positions `i` and `i + 1`
+    fn merge_at(&mut self, rec: &mut Recorder<T>, i: usize) {
+        let Run { base: mut base1, len: mut len1 } = self.runs[i];
+        let Run { base: base2, len: mut len2 } = self.runs[i + 1];
+        self.runs[i].len = len1 + len2;
+        self.runs.remove(i + 1);
+
+        rec.phase("gallop");
+        // Elements of run 1 already before run 2's first element stay put
+        let k = gallop_right(len1, 0, |k| rec.compare(base2, base1 + k));
+        base1 += k;
+        len1 -= k;
+        if len1 == 0 {
+            return;
+        }
+
+        // Elements of run 2 already after run 1's last element stay put
+        let last1 = base1 + len1 - 1;
+        len2 = gallop_left(len2, len2 - 1, |k| rec.compare(last1, base2 + k));
+        if len2 == 0 {
+            return;
+        }
+
+        let tmp_len = len1.min(len2);
+        rec.aux_alloc(tmp_len);
+        if len1 <= len2 {
+            self.merge_lo(rec, base1, len1, base2, len2);
+        } else {
+            self.merge_hi(rec, base1, len1, base2, len2);
+        }
+        rec.aux_free(tmp_len);
+    }
+
+    /// Copies `len` main-array elements starting at `base` into `tmp`
+    fn fill_tmp(&mut self, rec: &mut Recorder<T>, base: usize, len: usize) {
+        self.tmp.clear();
+        for k in 0..len {
+            let value = rec.get(base + k);
+            self.tmp.push(value.clone());
+            rec.aux_write(k, value);
+        }
+    }
+
+    /// Merges adjacent runs in place with run 1 (the shorter) copied to `tmp`
+    fn merge_lo(&mut self, rec: &mut Recorder<T>, base1: usize, mut len1: usize, base2: usize, mut len2: usize) {
+        rec.phase("merge");
+        self.fill_tmp(rec, base1, len1);
+        let mut cursor1 = 0;
+        let mut cursor2 = base2;
+        let mut dest = base1;
+
+        rec.write(dest, rec.get(cursor2));
+        dest += 1;
+        cursor2 += 1;
+        len2 -= 1;
+        if len2 == 0 {
+            self.copy_from_tmp(rec, cursor1, dest, len1);
+            return;
+        }
+        if len1 == 1 {
+            copy_within(rec, cursor2, dest, len2);
+            rec.write(dest + len2, self.tmp[cursor1].clone());
+            return;
+        }
+
+        let mut min_gallop = self.min_gallop;
+        'outer: loop {
+            // Straight merge until one run wins min_gallop times in a row
+            let mut count1 = 0;
+            let mut count2 = 0;
+            loop {
+                if rec.compare_value(cursor2, &self.tmp[cursor1], dest).is_lt() {
+                    rec.write(dest, rec.get(cursor2));
+                    dest += 1;
+                    cursor2 += 1;
+                    count2 += 1;
+                    count1 = 0;
+                    len2 -= 1;
+                    if len2 == 0 {
+                        break 'outer;
+                    }
+                } else {
+                    rec.write(dest, self.tmp[cursor1].clone());
+                    dest += 1;
+                    cursor1 += 1;
+                    count1 += 1;
+                    count2 = 0;
+                    len1 -= 1;
+                    if len1 == 1 {
+                        break 'outer;
+                    }
+                }
+                if (count1 | count2) >= min_gallop {
+                    break;
+                }
+            }
+
+            // One run is winning consistently, so gallop through it
+            rec.phase("gallop");
+            loop {
+                let tmp = &self.tmp;
+                count1 = gallop_right(len1, 0, |k| rec.compare_value(cursor2, &tmp[cursor1 + k], dest));
+                if count1 != 0 {
+                    self.copy_from_tmp(rec, cursor1, dest, count1);
+                    dest += count1;
+                    cursor1 += count1;
+                    len1 -= count1;
+                    if len1 <= 1 {
+                        break 'outer;
+                    }
+                }
+                rec.write(dest, rec.get(cursor2));
+                dest += 1;
+                cursor2 += 1;
+                len2 -= 1;
+                if len2 == 0 {
+                    break 'outer;
+                }
+
+                let key = self.tmp[cursor1].clone();
+                count2 = gallop_left(len2, 0, |k| rec.compare_value(cursor2 + k, &key, dest).reverse());
+                if count2 != 0 {
+                    copy_within(rec, cursor2, dest, count2);
+                    dest += count2;
+                    cursor2 += count2;
+                    len2 -= count2;
+                    if len2 == 0 {
+                        break 'outer;
+                    }
+                }
+                rec.write(dest, self.tmp[cursor1].clone());
+                dest += 1;
+                cursor1 += 1;
+                len1 -= 1;
+                if len1 == 1 {
+                    break 'outer;
+                }
+
+                min_gallop = min_gallop.saturating_sub(1);
+                if count1 < MIN_GALLOP && count2 < MIN_GALLOP {
+                    break;
+                }
+            }
+            // Penalize leaving gallop mode
+            min_gallop += 2;
+            rec.phase("merge");
+        }
+        self.min_gallop = min_gallop.max(1);
+
+        if len1 == 1 {
+            copy_within(rec, cursor2, dest, len2);
+            rec.write(dest + len2, self.tmp[cursor1].clone());
+        } else {
+            debug_assert!(len1 != 0, "comparison violates its general contract");
+            self.copy_from_tmp(rec, cursor1, dest, len1);
+        }
+    }
+
+    /// Merges adjacent runs in place with run 2 (the shorter) copied to `tmp`,
+    /// filling the result from the right end.
+    fn merge_hi(&mut self, rec: &mut Recorder<T>, base1: usize, mut len1: usize, base2: usize, mut len2: usize) {
+        rec.phase("merge");
+        self.fill_tmp(rec, base2, len2);
+        // Cursors move leftwards and may step one past the start of a run
+        let mut cursor1 = (base1 + len1 - 1) as isize;
+        let mut cursor2 = len2 as isize - 1;
+        let mut dest = (base2 + len2 - 1) as isize;
+
+        rec.write(dest as usize, rec.get(cursor1 as usize));
+        dest -= 1;
+        cursor1 -= 1;
+        len1 -= 1;
+        if len1 == 0 {
+            self.copy_from_tmp(rec, 0, (dest + 1) as usize - len2, len2);
+            return;
+        }
+        if len2 == 1 {
+            dest -= len1 as isize;
+            cursor1 -= len1 as isize;
+            copy_within(rec, (cursor1 + 1) as usize, (dest + 1) as usize, len1);
+            rec.write(dest as usize, self.tmp[cursor2 as usize].clone());
+            return;
+        }
+
+        let mut min_gallop = self.min_gallop;
+        'outer: loop {
+            let mut count1 = 0;
+            let mut count2 = 0;
+            loop {
+                let held = self.tmp[cursor2 as usize].clone();
+                if rec.compare_value(cursor1 as usize, &held, dest as usize).is_gt() {
+                    rec.write(dest as usize, rec.get(cursor1 as usize));
+                    dest -= 1;
+                    cursor1 -= 1;
+                    count1 += 1;
+                    count2 = 0;
+                    len1 -= 1;
+                    if len1 == 0 {
+                        break 'outer;
+                    }
+                } else {
+                    rec.write(dest as usize, held);
+                    dest -= 1;
+                    cursor2 -= 1;
+                    count2 += 1;
+                    count1 = 0;
+                    len2 -= 1;
+                    if len2 == 1 {
+                        break 'outer;
+                    }
+                }
+                if (count1 | count2) >= min_gallop {
+                    break;
+                }
+            }
+
+            rec.phase("gallop");
+            loop {
+                let key = self.tmp[cursor2 as usize].clone();
+                let hole = dest as usize;
+                count1 = len1 - gallop_right(len1, len1 - 1, |k| rec.compare_value(base1 + k, &key, hole).reverse());
+                if count1 != 0 {
+                    dest -= count1 as isize;
+                    cursor1 -= count1 as isize;
+                    len1 -= count1;
+                    copy_within(rec, (cursor1 + 1) as usize, (dest + 1) as usize, count1);
+                    if len1 == 0 {
+                        break 'outer;
+                    }
+                }
+                rec.write(dest as usize, self.tmp[cursor2 as usize].clone());
+                dest -= 1;
+                cursor2 -= 1;
+                len2 -= 1;
+                if len2 == 1 {
+                    break 'outer;
+                }
+
+                let tmp = &self.tmp;
+                let (key_index, hole) = (cursor1 as usize, dest as usize);
+                count2 = len2 - gallop_left(len2, len2 - 1, |k| rec.compare_value(key_index, &tmp[k], hole));
+                if count2 != 0 {
+                    dest -= count2 as isize;
+                    cursor2 -= count2 as isize;
+                    len2 -= count2;
+                    self.copy_from_tmp(rec, (cursor2 + 1) as usize, (dest + 1) as usize, count2);
+                    if len2 <= 1 {
+                        break 'outer;
+                    }
+                }
+                rec.write(dest as usize, rec.get(cursor1 as usize));
+                dest -= 1;
+                cursor1 -= 1;
+                len1 -= 1;
+                if len1 == 0 {
+                    break 'outer;
+                }
+
+                min_gallop = min_gallop.saturating_sub(1);
+                if count1 < MIN_GALLOP && count2 < MIN_GALLOP {
+                    break;
+                }
+            }
+            min_gallop += 2;
+            rec.phase("merge");
+        }
+        self.min_gallop = min_gallop.max(1);
+
+        if len2 == 1 {
+            dest -= len1 as isize;
+            cursor1 -= len1 as isize;
+            copy_within(rec, (cursor1 + 1) as usize, (dest + 1) as usize, len1);
+            rec.write(dest as usize, self.tmp[cursor2 as usize].clone());
+        } else {
+            debug_assert!(len2 != 0, "comparison violates its general contract");
+            self.copy_from_tmp(rec, 0, (dest + 1) as usize - len2, len2);
+        }
+    }
+
+    fn copy_from_tmp(&self, rec: &mut Recorder<T>, from: usize, dest: usize, len: usize) {
+        for k in 0..len {
+            rec.write(dest + k, self.tmp[from + k].clone());
+        }
+    }
+}
+
+/// `copy_within` on the main array, safe for overlapping ranges
+fn copy_within<T: Clone>(rec: &mut Recorder<T>, from: usize, dest: usize, len: usize) {
+    if dest <= from {
+        for k in 0..len {
+            rec.write(dest + k, rec.get(from + k));
+        }
+    } else {
+        for k in (0..len).rev() {
+            rec.write(dest + k, rec.get(from + k));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::Trace;
+    use crate::utils::{generate_random_list, is_sorted};
+
+    #[test]
+    fn test_sorts_large_and_structured_inputs() {
+        let mut inputs = vec![
+            generate_random_list(Some(1000), -500, 500, false),
+            (0..700).collect(),
+            (0..700).rev().collect(),
+        ];
+        // Many ascending runs of uneven length
+        inputs.push((0..900).map(|i| (i % 37) * 3 + i / 100).collect());
+
+        for input in inputs {
+            let mut data = input.clone();
+            Trace::record(&mut data, timsort);
+            let mut expected = input;
+            expected.sort();
+            assert!(is_sorted(&data));
+            assert_eq!(data, expected);
+        }
+    }
+
+    #[test]
+    fn test_sorted_input_needs_no_writes() {
+        let mut data: Vec<i32> = (0..500).collect();
+        let trace = Trace::record(&mut data, timsort);
+        assert_eq!(trace.counters.compares, 499);
+        assert_eq!(trace.counters.writes, 0);
+        assert_eq!(trace.counters.aux_peak, 0);
+    }
+
+    #[test]
+    fn test_min_run_length() {
+        assert_eq!(min_run_length(31), 31);
+        assert_eq!(min_run_length(64), 16);
+        assert_eq!(min_run_length(65), 17);
+    }
+}
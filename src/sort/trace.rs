@@ -0,0 +1,371 @@
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use super::counters::Counters;
+
+/// A single step performed by a sorting algorithm.
+///
+/// Indices always refer to the whole array being sorted, never to a sub-slice.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortEvent<T = i32> {
+    /// Two elements of the main array were compared
+    Compare(usize, usize),
+    /// Two elements of the main array were swapped
+    Swap(usize, usize),
+    /// A value was written into the main array
+    Write(usize, T),
+    /// A value was written into the auxiliary buffer
+    AuxWrite(usize, T),
+    /// The element at this index was chosen as a pivot
+    Pivot(usize),
+    /// These positions hold their final values
+    MarkSorted(Range<usize>),
+    /// The algorithm entered a new named phase
+    PhaseStart(String),
+}
+
+impl<T: Clone + Default> SortEvent<T> {
+    /// Applies the event to a copy of the array and its auxiliary buffer,
+    /// so a trace can be replayed from the initial data.
+    pub fn apply(&self, data: &mut [T], aux: &mut Vec<T>) {
+        match self {
+            SortEvent::Swap(i, j) => data.swap(*i, *j),
+            SortEvent::Write(i, value) => data[*i] = value.clone(),
+            SortEvent::AuxWrite(i, value) => {
+                if aux.len() <= *i {
+                    aux.resize(*i + 1, T::default());
+                }
+                aux[*i] = value.clone();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T> SortEvent<T> {
+    /// The same event with written values converted by `f`, e.g. records
+    /// reduced to their keys for display
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> SortEvent<U> {
+        match self {
+            SortEvent::Compare(i, j) => SortEvent::Compare(*i, *j),
+            SortEvent::Swap(i, j) => SortEvent::Swap(*i, *j),
+            SortEvent::Write(i, value) => SortEvent::Write(*i, f(value)),
+            SortEvent::AuxWrite(i, value) => SortEvent::AuxWrite(*i, f(value)),
+            SortEvent::Pivot(i) => SortEvent::Pivot(*i),
+            SortEvent::MarkSorted(range) => SortEvent::MarkSorted(range.clone()),
+            SortEvent::PhaseStart(name) => SortEvent::PhaseStart(name.clone()),
+        }
+    }
+
+    /// Indices of the main array touched by the event
+    pub fn indices(&self) -> Vec<usize> {
+        match self {
+            SortEvent::Compare(i, j) | SortEvent::Swap(i, j) => vec![*i, *j],
+            SortEvent::Write(i, _) | SortEvent::Pivot(i) => vec![*i],
+            SortEvent::MarkSorted(range) => range.clone().collect(),
+            SortEvent::AuxWrite(..) | SortEvent::PhaseStart(_) => Vec::new(),
+        }
+    }
+}
+
+/// Order used by the recorder; `Ord::cmp` unless a comparator is given
+type Comparator<'a, T> = Box<dyn Fn(&T, &T) -> Ordering + 'a>;
+
+/// Instrumented view of the array that algorithms sort through.
+///
+/// Every comparison and modification goes through the recorder, which
+/// performs it on the real data, updates the [`Counters`] and, unless it
+/// only counts, appends the matching [`SortEvent`].
+///
+/// Elements are `i32` by default, which is what the visualizations show;
+/// any `Ord` type, or any type with a comparator, can be sorted as well.
+pub struct Recorder<'a, T = i32> {
+    data: &'a mut [T],
+    cmp: Comparator<'a, T>,
+    events: Vec<SortEvent<T>>,
+    record_events: bool,
+    counters: Counters,
+    // `get` takes `&self` so it can be used inside `write` arguments
+    reads: Cell<usize>,
+    aux_in_use: usize,
+}
+
+impl<'a, T: Ord> Recorder<'a, T> {
+    pub fn new(data: &'a mut [T]) -> Self {
+        Self::with_comparator(data, T::cmp)
+    }
+}
+
+impl<'a, T> Recorder<'a, T> {
+    /// Sorts by `cmp` instead of `Ord`, e.g. `f64::total_cmp` for floats
+    /// or a key of a struct
+    pub fn with_comparator(data: &'a mut [T], cmp: impl Fn(&T, &T) -> Ordering + 'a) -> Self {
+        Self {
+            data,
+            cmp: Box::new(cmp),
+            events: Vec::new(),
+            record_events: true,
+            counters: Counters::default(),
+            reads: Cell::new(0),
+            aux_in_use: 0,
+        }
+    }
+
+    /// Stops storing events; counters are still updated
+    pub fn without_events(mut self) -> Self {
+        self.record_events = false;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn compare(&mut self, i: usize, j: usize) -> Ordering {
+        self.emit(SortEvent::Compare(i, j));
+        *self.reads.get_mut() += 2;
+        (self.cmp)(&self.data[i], &self.data[j])
+    }
+
+    /// Compares `data[i]` with a value the algorithm holds outside the array,
+    /// such as an element lifted out for insertion. `hole` is the position
+    /// the held value is destined for and is shown as the second operand.
+    pub fn compare_value(&mut self, i: usize, value: &T, hole: usize) -> Ordering {
+        self.emit(SortEvent::Compare(i, hole));
+        *self.reads.get_mut() += 1;
+        (self.cmp)(&self.data[i], value)
+    }
+
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.data.swap(i, j);
+        self.emit(SortEvent::Swap(i, j));
+    }
+
+    /// Reports that the algorithm allocated `len` auxiliary elements
+    pub fn aux_alloc(&mut self, len: usize) {
+        self.aux_in_use += len;
+        self.counters.aux_peak = self.counters.aux_peak.max(self.aux_in_use);
+    }
+
+    /// Reports that `len` auxiliary elements were released
+    pub fn aux_free(&mut self, len: usize) {
+        self.aux_in_use = self.aux_in_use.saturating_sub(len);
+    }
+
+    pub fn pivot(&mut self, i: usize) {
+        self.emit(SortEvent::Pivot(i));
+    }
+
+    pub fn mark_sorted(&mut self, range: Range<usize>) {
+        if !range.is_empty() {
+            self.emit(SortEvent::MarkSorted(range));
+        }
+    }
+
+    pub fn phase(&mut self, name: impl Into<String>) {
+        if self.record_events {
+            self.events.push(SortEvent::PhaseStart(name.into()));
+        }
+    }
+
+    pub fn counters(&self) -> Counters {
+        Counters {
+            reads: self.reads.get(),
+            ..self.counters
+        }
+    }
+
+    pub fn into_events(self) -> Vec<SortEvent<T>> {
+        self.events
+    }
+
+    fn emit(&mut self, event: SortEvent<T>) {
+        self.counters.record(&event);
+        if self.record_events {
+            self.events.push(event);
+        }
+    }
+}
+
+impl<T: Clone> Recorder<'_, T> {
+    /// Reads an element; counted, but not recorded as a step
+    pub fn get(&self, i: usize) -> T {
+        self.reads.set(self.reads.get() + 1);
+        self.data[i].clone()
+    }
+
+    pub fn write(&mut self, i: usize, value: T) {
+        if self.record_events {
+            self.data[i] = value.clone();
+            self.emit(SortEvent::Write(i, value));
+        } else {
+            self.data[i] = value;
+            self.counters.writes += 1;
+        }
+    }
+
+    pub fn aux_write(&mut self, i: usize, value: T) {
+        if self.record_events {
+            self.emit(SortEvent::AuxWrite(i, value));
+        } else {
+            self.counters.aux_writes += 1;
+        }
+    }
+}
+
+/// Phase of a hybrid sort that switches between strategies: a phase is
+/// recorded only when the strategy actually changes
+#[derive(Default)]
+pub(super) struct PhaseTracker {
+    current: Option<&'static str>,
+}
+
+impl PhaseTracker {
+    pub(super) fn switch<T>(&mut self, rec: &mut Recorder<T>, phase: &'static str) {
+        if self.current != Some(phase) {
+            self.current = Some(phase);
+            rec.phase(phase);
+        }
+    }
+}
+
+/// A recorded sorting run: the input array and every step taken on it
+pub struct Trace<T = i32> {
+    pub initial: Vec<T>,
+    pub events: Vec<SortEvent<T>>,
+    pub counters: Counters,
+}
+
+impl<T> Trace<T> {
+    /// The same run with elements converted by `f`, e.g. records reduced
+    /// to their keys for replaying or exporting
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Trace<U> {
+        Trace {
+            initial: self.initial.iter().map(&f).collect(),
+            events: self.events.iter().map(|event| event.map(&f)).collect(),
+            counters: self.counters,
+        }
+    }
+}
+
+impl<T: Clone + Default> Trace<T> {
+    /// The array after every event has been applied to the initial data
+    pub fn final_data(&self) -> Vec<T> {
+        let mut data = self.initial.clone();
+        let mut aux = Vec::new();
+        for event in &self.events {
+            event.apply(&mut data, &mut aux);
+        }
+        data
+    }
+}
+
+impl<T: Ord + Clone> Trace<T> {
+    /// Sorts `data` in place with `sort` and returns the recorded steps
+    pub fn record<F>(data: &mut [T], sort: F) -> Self
+    where
+        F: FnOnce(&mut Recorder<T>),
+    {
+        Self::record_by(data, T::cmp, sort)
+    }
+
+    /// Sorts `data` in place and returns only the counters, without
+    /// storing events
+    pub fn count<F>(data: &mut [T], sort: F) -> Counters
+    where
+        F: FnOnce(&mut Recorder<T>),
+    {
+        Self::count_by(data, T::cmp, sort)
+    }
+}
+
+impl<T: Clone> Trace<T> {
+    /// Like [`Trace::record`], ordering elements by `cmp`
+    pub fn record_by<C, F>(data: &mut [T], cmp: C, sort: F) -> Self
+    where
+        C: Fn(&T, &T) -> Ordering,
+        F: FnOnce(&mut Recorder<T>),
+    {
+        let initial = data.to_vec();
+        let mut recorder = Recorder::with_comparator(data, cmp);
+        sort(&mut recorder);
+        let counters = recorder.counters();
+        Self {
+            initial,
+            events: recorder.into_events(),
+            counters,
+        }
+    }
+
+    /// Like [`Trace::count`], ordering elements by `cmp`
+    pub fn count_by<C, F>(data: &mut [T], cmp: C, sort: F) -> Counters
+    where
+        C: Fn(&T, &T) -> Ordering,
+        F: FnOnce(&mut Recorder<T>),
+    {
+        let mut recorder = Recorder::with_comparator(data, cmp).without_events();
+        sort(&mut recorder);
+        recorder.counters()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_reproduces_result() {
+        let mut data = vec![4, 1, 3, 2];
+        let trace = Trace::record(&mut data, |rec| {
+            rec.swap(0, 1);
+            rec.write(2, 7);
+            rec.aux_write(0, 4);
+        });
+
+        let mut replayed = trace.initial.clone();
+        let mut aux = Vec::new();
+        for event in &trace.events {
+            event.apply(&mut replayed, &mut aux);
+        }
+        assert_eq!(replayed, data);
+        assert_eq!(aux, vec![4]);
+    }
+
+    #[test]
+    fn test_counts_by_kind() {
+        let mut data = vec![2, 1];
+        let trace = Trace::record(&mut data, |rec| {
+            if rec.compare(0, 1) == Ordering::Greater {
+                rec.swap(0, 1);
+            }
+        });
+        assert_eq!(trace.counters.compares, 1);
+        assert_eq!(trace.counters.swaps, 1);
+        assert_eq!(trace.counters.reads, 2);
+        assert_eq!(data, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_counting_matches_recording() {
+        let sort = |rec: &mut Recorder| {
+            rec.aux_alloc(3);
+            let first = rec.get(0);
+            rec.aux_write(0, first);
+            rec.aux_free(3);
+            rec.aux_alloc(2);
+            rec.write(1, first);
+        };
+        let trace = Trace::record(&mut [5, 6], sort);
+        let counters = Trace::count(&mut [5, 6], sort);
+        assert_eq!(trace.counters, counters);
+        assert_eq!(counters.reads, 1);
+        assert_eq!(counters.writes, 1);
+        assert_eq!(counters.aux_writes, 1);
+        assert_eq!(counters.aux_peak, 3);
+    }
+}
@@ -0,0 +1,21 @@
+/// Xorshift from "Xorshift RNGs" by George Marsaglia.
+///
+/// A tiny deterministic generator for the algorithms that pick positions
+/// at random: the same seed always gives the same steps, so a recorded
+/// sort can be reproduced.
+pub(super) struct XorShift(u32);
+
+impl XorShift {
+    pub(super) fn new(seed: u64) -> Self {
+        // Xorshift never leaves zero, so that state is replaced
+        let state = (seed ^ (seed >> 32)) as u32;
+        Self(state.max(1))
+    }
+
+    pub(super) fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as usize
+    }
+}
@@ -1,163 +1,482 @@
-use rand::Rng;
-use std::fs::File;
-use std::io::{BufRead, BufReader, Result};
-use std::collections::HashSet;
-
-/// Генерирует случайный вектор чисел
-pub fn generate_random_list(count: Option<usize>, min: i32, max: i32, no_repeats: bool) -> Vec<i32> {
-    let mut rng = rand::thread_rng();
-    let count = count.unwrap_or((max - min + 1) as usize);
-    
-    if no_repeats {
-        let mut used = HashSet::new();
-        let mut result = Vec::with_capacity(count);
-        let range_size = (max - min + 1) as usize;
-        
-        if count > range_size {
-            // Если запрошено больше уникальных чисел, чем доступно в диапазоне
-            for i in min..=max {
-                result.push(i);
-            }
-            // Добавляем случайные числа из диапазона до нужного количества
-            while result.len() < count {
-                let num = rng.gen_range(min..=max);
-                result.push(num);
-            }
-        } else {
-            while result.len() < count {
-                let num = rng.gen_range(min..=max);
-                if used.insert(num) {
-                    result.push(num);
-                }
-            }
-        }
-        result
-    } else {
-        (0..count)
-            .map(|_| rng.gen_range(min..=max))
-            .collect()
-    }
-}
-
-/// Читает список чисел из файла
-pub fn read_list_from_file(filename: &str) -> Result<Vec<i32>> {
-    let file = File::open(filename)?;
-    let reader = BufReader::new(file);
-    let mut numbers = Vec::new();
-    
-    for line in reader.lines() {
-        let line = line?;
-        for num_str in line.split_whitespace() {
-            if let Ok(num) = num_str.parse::<i32>() {
-                numbers.push(num);
-            }
-        }
-    }
-    
-    Ok(numbers)
-}
-
-/// Проверяет, отсортирован ли вектор
-pub fn is_sorted(data: &[i32]) -> bool {
-    data.windows(2).all(|w| w[0] <= w[1])
-}
-
-/// Создает вектор маркеров для визуализации
-pub fn create_markers(len: usize, active_indices: &[usize]) -> Vec<bool> {
-    let mut markers = vec![false; len];
-    for &index in active_indices {
-        if index < len {
-            markers[index] = true;
-        }
-    }
-    markers
-}
-
-/// Клонирует вектор с возможностью изменения отдельных элементов
-pub fn clone_with_modifications(data: &[i32], modifications: &[(usize, i32)]) -> Vec<i32> {
-    let mut result = data.to_vec();
-    for &(index, value) in modifications {
-        if index < result.len() {
-            result[index] = value;
-        }
-    }
-    result
-}
-
-/// Находит минимальный и максимальный элементы в векторе
-pub fn find_min_max(data: &[i32]) -> (i32, i32) {
-    if data.is_empty() {
-        return (0, 0);
-    }
-    
-    let mut min = data[0];
-    let mut max = data[0];
-    
-    for &value in data.iter().skip(1) {
-        if value < min {
-            min = value;
-        }
-        if value > max {
-            max = value;
-        }
-    }
-    
-    (min, max)
-}
-
-/// Проверяет, содержит ли вектор только уникальные элементы
-pub fn has_unique_elements(data: &[i32]) -> bool {
-    let mut seen = HashSet::new();
-    for &item in data {
-        if !seen.insert(item) {
-            return false;
-        }
-    }
-    true
-}
-
-/// Преобразует вектор в строку для отладки
-pub fn vec_to_string(data: &[i32]) -> String {
-    format!("[{}]", data.iter()
-        .map(|x| x.to_string())
-        .collect::<Vec<String>>()
-        .join(", "))
-}
-
-/// Получает подвектор по индексам
-pub fn get_subvector(data: &[i32], start: usize, end: usize) -> Vec<i32> {
-    if start >= data.len() || end > data.len() || start >= end {
-        return Vec::new();
-    }
-    data[start..end].to_vec()
-}
-
-/// Объединяет два вектора
-pub fn merge_vectors(left: Vec<i32>, right: Vec<i32>) -> Vec<i32> {
-    let mut result = left;
-    result.extend(right);
-    result
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_generate_random_list() {
-        let list = generate_random_list(Some(10), 1, 100, false);
-        assert_eq!(list.len(), 10);
-    }
-
-    #[test]
-    fn test_is_sorted() {
-        assert!(is_sorted(&[1, 2, 3, 4, 5]));
-        assert!(!is_sorted(&[5, 2, 3, 1, 4]));
-    }
-
-    #[test]
-    fn test_create_markers() {
-        let markers = create_markers(5, &[0, 2, 4]);
-        assert_eq!(markers, vec![true, false, true, false, true]);
-    }
-}
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Result};
+use std::collections::HashSet;
+use std::cell::RefCell;
+
+use crate::sort::{AlgorithmSpec, Trace};
+
+/// Генерирует случайный вектор чисел
+pub fn generate_random_list(count: Option<usize>, min: i32, max: i32, no_repeats: bool) -> Vec<i32> {
+    let count = count.unwrap_or(range_size(min, max));
+    random_values(&mut rand::rng(), count, min, max, no_repeats)
+}
+
+/// Количество чисел в `min..=max`; считается в `i64`, так как диапазон
+/// может занимать почти весь `i32`
+fn range_size(min: i32, max: i32) -> usize {
+    (i64::from(max) - i64::from(min) + 1) as usize
+}
+
+fn random_values(rng: &mut impl Rng, count: usize, min: i32, max: i32, no_repeats: bool) -> Vec<i32> {
+    if no_repeats {
+        let mut used = HashSet::new();
+        let mut result = Vec::with_capacity(count);
+        if count > range_size(min, max) {
+            // Если запрошено больше уникальных чисел, чем доступно в диапазоне
+            for i in min..=max {
+                result.push(i);
+            }
+            // Добавляем случайные числа из диапазона до нужного количества
+            while result.len() < count {
+                let num = rng.random_range(min..=max);
+                result.push(num);
+            }
+        } else {
+            while result.len() < count {
+                let num = rng.random_range(min..=max);
+                if used.insert(num) {
+                    result.push(num);
+                }
+            }
+        }
+        result
+    } else {
+        (0..count)
+            .map(|_| rng.random_range(min..=max))
+            .collect()
+    }
+}
+
+/// Форма входного массива для `.generate ... shape=<name> [k=<n>]`.
+/// У части форм есть параметр `k`; если он не задан, берётся значение
+/// по умолчанию, зависящее от размера массива.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Distribution {
+    /// Равномерно случайные значения
+    #[default]
+    Uniform,
+    Sorted,
+    Reversed,
+    /// Отсортированный массив после `k` случайных обменов
+    NearlySorted(Option<usize>),
+    /// Только `k` различных значений
+    FewUnique(Option<usize>),
+    /// `k` возрастающих «зубцов» от min до max
+    Sawtooth(Option<usize>),
+    /// Возрастает до середины, затем убывает
+    OrganPipe,
+    /// Нормальное распределение с центром в середине диапазона
+    Gaussian,
+    AllEqual,
+    /// Отсортированные серии случайной длины, не длиннее `k`
+    Runs(Option<usize>),
+}
+
+impl Distribution {
+    /// Имена форм для справки и сообщений об ошибках
+    pub const NAMES: &'static [&'static str] = &[
+        "uniform", "sorted", "reversed", "nearlysorted", "fewunique",
+        "sawtooth", "organpipe", "gaussian", "allequal", "runs",
+    ];
+
+    /// Находит форму по имени или сокращению, `k` передаётся формам с параметром
+    pub fn from_name(name: &str, k: Option<usize>) -> Option<Self> {
+        let shape = match name.to_ascii_lowercase().as_str() {
+            "uniform" | "random" => Self::Uniform,
+            "sorted" => Self::Sorted,
+            "reversed" | "reverse" => Self::Reversed,
+            "nearlysorted" | "nearly" => Self::NearlySorted(k),
+            "fewunique" | "few" => Self::FewUnique(k),
+            "sawtooth" => Self::Sawtooth(k),
+            "organpipe" | "pipe" => Self::OrganPipe,
+            "gaussian" | "normal" => Self::Gaussian,
+            "allequal" | "equal" => Self::AllEqual,
+            "runs" => Self::Runs(k),
+            _ => return None,
+        };
+        Some(shape)
+    }
+
+    /// Генерирует `count` чисел из `min..=max`; `no_repeats` учитывается
+    /// формами, построенными из равномерной выборки
+    pub fn generate(self, rng: &mut impl Rng, count: usize, min: i32, max: i32, no_repeats: bool) -> Vec<i32> {
+        match self {
+            Self::Uniform => random_values(rng, count, min, max, no_repeats),
+            Self::Sorted => {
+                let mut data = random_values(rng, count, min, max, no_repeats);
+                data.sort_unstable();
+                data
+            }
+            Self::Reversed => {
+                let mut data = random_values(rng, count, min, max, no_repeats);
+                data.sort_unstable_by(|a, b| b.cmp(a));
+                data
+            }
+            Self::NearlySorted(k) => {
+                let mut data = random_values(rng, count, min, max, no_repeats);
+                data.sort_unstable();
+                if count > 1 {
+                    for _ in 0..k.unwrap_or((count / 20).max(1)) {
+                        data.swap(rng.random_range(0..count), rng.random_range(0..count));
+                    }
+                }
+                data
+            }
+            Self::FewUnique(k) => {
+                let values = random_values(rng, k.unwrap_or(4).max(1), min, max, true);
+                (0..count).map(|_| values[rng.random_range(0..values.len())]).collect()
+            }
+            Self::Sawtooth(k) => {
+                let tooth = count.div_ceil(k.unwrap_or(4).max(1)).max(1);
+                (0..count).map(|i| lerp(min, max, i % tooth, tooth)).collect()
+            }
+            Self::OrganPipe => {
+                let half = count.div_ceil(2);
+                (0..count).map(|i| lerp(min, max, i.min(count - 1 - i), half)).collect()
+            }
+            Self::Gaussian => {
+                let mean = (f64::from(min) + f64::from(max)) / 2.0;
+                let std_dev = (f64::from(max) - f64::from(min)) / 6.0;
+                (0..count)
+                    .map(|_| {
+                        // Преобразование Бокса — Мюллера
+                        let u1: f64 = 1.0 - rng.random::<f64>();
+                        let u2: f64 = rng.random();
+                        let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+                        (mean + z * std_dev).round().clamp(f64::from(min), f64::from(max)) as i32
+                    })
+                    .collect()
+            }
+            Self::AllEqual => vec![((i64::from(min) + i64::from(max)) / 2) as i32; count],
+            Self::Runs(k) => {
+                let max_run = k.unwrap_or((count / 8).max(2)).max(1);
+                let mut data = random_values(rng, count, min, max, no_repeats);
+                let mut start = 0;
+                while start < count {
+                    let end = (start + rng.random_range(1..=max_run)).min(count);
+                    data[start..end].sort_unstable();
+                    start = end;
+                }
+                data
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Distribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (name, k) = match *self {
+            Self::Uniform => ("uniform", None),
+            Self::Sorted => ("sorted", None),
+            Self::Reversed => ("reversed", None),
+            Self::NearlySorted(k) => ("nearlysorted", k),
+            Self::FewUnique(k) => ("fewunique", k),
+            Self::Sawtooth(k) => ("sawtooth", k),
+            Self::OrganPipe => ("organpipe", None),
+            Self::Gaussian => ("gaussian", None),
+            Self::AllEqual => ("allequal", None),
+            Self::Runs(k) => ("runs", k),
+        };
+        write!(f, "{}", name)?;
+        if let Some(k) = k {
+            write!(f, " k={}", k)?;
+        }
+        Ok(())
+    }
+}
+
+/// Линейно переводит позицию `i` из `0..len` в значение из `min..=max`
+fn lerp(min: i32, max: i32, i: usize, len: usize) -> i32 {
+    if len < 2 {
+        return min;
+    }
+    let span = i64::from(max) - i64::from(min);
+    (i64::from(min) + span * i as i64 / (len as i64 - 1)) as i32
+}
+
+/// Худший вход для алгоритма сравнениями — противник МакИлроя
+/// («A Killer Adversary for Quicksort», 1999).
+///
+/// Алгоритм сортирует `count` элементов, значения которых ещё не выбраны:
+/// все они «газ», больше любого «твёрдого» значения. Когда сравниваются два
+/// газа, один из них застывает в следующее по порядку, то есть наименьшее
+/// из ещё не выданных, твёрдое значение: застывает кандидат в опорные (газ,
+/// участвовавший в прошлом сравнении), если он в паре, иначе второй элемент.
+/// Опорный элемент быстрой сортировки сравнивается подряд со многими, поэтому
+/// застывает именно он и оказывается меньше всего оставшегося газа: каждое
+/// разбиение отделяет лишь его самого. Оставшийся после сортировки газ
+/// застывает по порядку, ответы сравнений при этом не меняются.
+///
+/// Возвращает перестановку `0..count`, на которой алгоритм сделает те же
+/// сравнения, или `None`, если алгоритм не сравнивает элементы (counting,
+/// radix).
+pub fn antiqsort(count: usize, spec: &AlgorithmSpec) -> Option<Vec<i32>> {
+    struct Adversary {
+        values: Vec<usize>,
+        solid: usize,
+        candidate: usize,
+    }
+
+    let gas = count;
+    let adversary = RefCell::new(Adversary { values: vec![gas; count], solid: 0, candidate: 0 });
+    let compare = |x: &i32, y: &i32| {
+        let (x, y) = (*x as usize, *y as usize);
+        let mut state = adversary.borrow_mut();
+        if state.values[x] == gas && state.values[y] == gas {
+            let frozen = if x == state.candidate { x } else { y };
+            state.values[frozen] = state.solid;
+            state.solid += 1;
+        }
+        if state.values[x] == gas {
+            state.candidate = x;
+        } else if state.values[y] == gas {
+            state.candidate = y;
+        }
+        state.values[x].cmp(&state.values[y])
+    };
+
+    // Элементы — номера позиций во входе, сравниваются их будущие значения
+    let mut items: Vec<i32> = (0..count as i32).collect();
+    let counters = Trace::count_by(&mut items, compare, |rec| spec.run(rec));
+    if count > 1 && counters.compares == 0 {
+        return None;
+    }
+
+    let mut state = adversary.into_inner();
+    for value in &mut state.values {
+        if *value == gas {
+            *value = state.solid;
+            state.solid += 1;
+        }
+    }
+    Some(state.values.into_iter().map(|value| value as i32).collect())
+}
+
+/// Читает список чисел из файла
+pub fn read_list_from_file(filename: &str) -> Result<Vec<i32>> {
+    let file = File::open(filename)?;
+    let reader = BufReader::new(file);
+    let mut numbers = Vec::new();
+    
+    for line in reader.lines() {
+        let line = line?;
+        for num_str in line.split_whitespace() {
+            if let Ok(num) = num_str.parse::<i32>() {
+                numbers.push(num);
+            }
+        }
+    }
+    
+    Ok(numbers)
+}
+
+/// Проверяет, отсортирован ли вектор
+pub fn is_sorted(data: &[i32]) -> bool {
+    data.windows(2).all(|w| w[0] <= w[1])
+}
+
+/// Создает вектор маркеров для визуализации
+pub fn create_markers(len: usize, active_indices: &[usize]) -> Vec<bool> {
+    let mut markers = vec![false; len];
+    for &index in active_indices {
+        if index < len {
+            markers[index] = true;
+        }
+    }
+    markers
+}
+
+/// Клонирует вектор с возможностью изменения отдельных элементов
+pub fn clone_with_modifications(data: &[i32], modifications: &[(usize, i32)]) -> Vec<i32> {
+    let mut result = data.to_vec();
+    for &(index, value) in modifications {
+        if index < result.len() {
+            result[index] = value;
+        }
+    }
+    result
+}
+
+/// Находит минимальный и максимальный элементы в векторе
+pub fn find_min_max(data: &[i32]) -> (i32, i32) {
+    if data.is_empty() {
+        return (0, 0);
+    }
+    
+    let mut min = data[0];
+    let mut max = data[0];
+    
+    for &value in data.iter().skip(1) {
+        if value < min {
+            min = value;
+        }
+        if value > max {
+            max = value;
+        }
+    }
+    
+    (min, max)
+}
+
+/// Проверяет, содержит ли вектор только уникальные элементы
+pub fn has_unique_elements(data: &[i32]) -> bool {
+    let mut seen = HashSet::new();
+    for &item in data {
+        if !seen.insert(item) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Преобразует вектор в строку для отладки
+pub fn vec_to_string(data: &[i32]) -> String {
+    format!("[{}]", data.iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>()
+        .join(", "))
+}
+
+/// Получает подвектор по индексам
+pub fn get_subvector(data: &[i32], start: usize, end: usize) -> Vec<i32> {
+    if start >= data.len() || end > data.len() || start >= end {
+        return Vec::new();
+    }
+    data[start..end].to_vec()
+}
+
+/// Объединяет два вектора
+pub fn merge_vectors(left: Vec<i32>, right: Vec<i32>) -> Vec<i32> {
+    let mut result = left;
+    result.extend(right);
+    result
+}
+
+/// Набор операций над массивом, которые использует приложение
+pub struct ArrayUtils;
+
+impl ArrayUtils {
+    /// Массив, с которым приложение стартует
+    pub fn default_array() -> Vec<i32> {
+        vec![5, 3, 8, 1, 9, 2, 7, 4, 6, 10]
+    }
+
+    /// Один и тот же `seed` всегда даёт один и тот же массив
+    pub fn generate_array(
+        count: usize,
+        min: i32,
+        max: i32,
+        no_repeats: bool,
+        distribution: Distribution,
+        seed: u64,
+    ) -> Vec<i32> {
+        distribution.generate(&mut StdRng::seed_from_u64(seed), count, min, max, no_repeats)
+    }
+
+    /// Перемешивание воспроизводимо: тот же массив и `seed` дают тот же результат
+    pub fn shuffle_array(data: &mut [i32], seed: u64) {
+        data.shuffle(&mut StdRng::seed_from_u64(seed));
+    }
+
+    pub fn read_array_from_file(filename: &str) -> Result<Vec<i32>> {
+        read_list_from_file(filename)
+    }
+
+    pub fn array_to_string(data: &[i32]) -> String {
+        vec_to_string(data)
+    }
+
+    pub fn clone_array(data: &[i32]) -> Vec<i32> {
+        data.to_vec()
+    }
+
+    /// Массив из 0 или 1 элемента сортировать не нужно
+    pub fn is_trivial_array(data: &[i32]) -> bool {
+        data.len() < 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_random_list() {
+        let list = generate_random_list(Some(10), 1, 100, false);
+        assert_eq!(list.len(), 10);
+    }
+
+    #[test]
+    fn test_distributions_stay_in_range() {
+        for name in Distribution::NAMES {
+            let shape = Distribution::from_name(name, None).unwrap();
+            let list = shape.generate(&mut rand::rng(), 101, -20, 20, false);
+            assert_eq!(list.len(), 101, "{}", name);
+            assert!(list.iter().all(|v| (-20..=20).contains(v)), "{}: {:?}", name, list);
+        }
+
+        let sorted = Distribution::Sorted.generate(&mut rand::rng(), 50, 0, 100, false);
+        assert!(is_sorted(&sorted));
+        let pipe = Distribution::OrganPipe.generate(&mut rand::rng(), 9, 0, 8, false);
+        assert_eq!(pipe, vec![0, 2, 4, 6, 8, 6, 4, 2, 0]);
+        let few = Distribution::FewUnique(Some(3)).generate(&mut rand::rng(), 100, 0, 1000, false);
+        assert!(few.iter().collect::<HashSet<_>>().len() <= 3);
+    }
+
+    #[test]
+    fn test_full_i32_range() {
+        let list = ArrayUtils::generate_array(5, -2_000_000_000, 2_000_000_000, true, Distribution::Uniform, 1);
+        assert_eq!(list.len(), 5);
+        assert!(has_unique_elements(&list));
+        let list = generate_random_list(Some(5), i32::MIN, i32::MAX, true);
+        assert_eq!(list.len(), 5);
+    }
+
+    #[test]
+    fn test_seed_reproduces_array() {
+        let shape = Distribution::NearlySorted(None);
+        let first = ArrayUtils::generate_array(200, -100, 100, false, shape, 42);
+        assert_eq!(first, ArrayUtils::generate_array(200, -100, 100, false, shape, 42));
+        assert_ne!(first, ArrayUtils::generate_array(200, -100, 100, false, shape, 43));
+
+        let (mut a, mut b) = (first.clone(), first);
+        ArrayUtils::shuffle_array(&mut a, 7);
+        ArrayUtils::shuffle_array(&mut b, 7);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_antiqsort_makes_quicksort_quadratic() {
+        let quick = crate::sort::find("quick").unwrap();
+        for pivot in ["last", "middle", "random", "median3", "ninther"] {
+            let mut spec = AlgorithmSpec::new(quick);
+            spec.set_option(&format!("pivot={}", pivot)).unwrap();
+            let data = antiqsort(500, &spec).unwrap();
+            let mut sorted = data.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..500).collect::<Vec<_>>());
+
+            // На найденном входе те же сравнения повторяются и на настоящих числах
+            let compares = Trace::count(&mut data.clone(), |rec| spec.run(rec)).compares;
+            assert!(compares > 500 * 500 / 16, "{}: {}", pivot, compares);
+        }
+
+        let radix = AlgorithmSpec::new(crate::sort::find("radix").unwrap());
+        assert_eq!(antiqsort(100, &radix), None);
+    }
+
+    #[test]
+    fn test_is_sorted() {
+        assert!(is_sorted(&[1, 2, 3, 4, 5]));
+        assert!(!is_sorted(&[5, 2, 3, 1, 4]));
+    }
+
+    #[test]
+    fn test_create_markers() {
+        let markers = create_markers(5, &[0, 2, 4]);
+        assert_eq!(markers, vec![true, false, true, false, true]);
+    }
+}
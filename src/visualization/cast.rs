@@ -0,0 +1,82 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Паузы между командами длиннее этой сжимаются при воспроизведении
+const IDLE_TIME_LIMIT: f64 = 2.0;
+
+/// Запись вывода в формате asciinema v2: строка-заголовок с размером
+/// терминала, затем по строке `[время, "o", "данные"]` на каждый кадр
+pub struct CastWriter {
+    file: BufWriter<File>,
+    start: Instant,
+    frames: usize,
+}
+
+impl CastWriter {
+    pub fn create(path: impl AsRef<Path>, width: usize, height: usize) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        writeln!(
+            file,
+            r#"{{"version": 2, "width": {}, "height": {}, "timestamp": {}, "idle_time_limit": {:.1}, "env": {{"TERM": "xterm-256color"}}}}"#,
+            width, height, timestamp, IDLE_TIME_LIMIT
+        )?;
+        Ok(Self { file, start: Instant::now(), frames: 0 })
+    }
+
+    /// Записывает вывод кадра с временем от начала записи
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(bytes);
+        writeln!(
+            self.file,
+            r#"[{:.6}, "o", "{}"]"#,
+            self.start.elapsed().as_secs_f64(),
+            escape_json(&text)
+        )?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Дописывает файл и возвращает число записанных кадров
+    pub fn finish(mut self) -> io::Result<usize> {
+        self.file.flush()?;
+        Ok(self.frames)
+    }
+}
+
+/// Строка JSON; перевод строки дополняется возвратом каретки, как его
+/// выводит терминал, иначе плеер рисует строки лесенкой
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut previous = None;
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' if previous == Some('\r') => escaped.push_str("\\n"),
+            '\n' => escaped.push_str("\\r\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+        previous = Some(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_json() {
+        assert_eq!(escape_json("\x1b[2Ja\"b\\\n"), "\\u001b[2Ja\\\"b\\\\\\r\\n");
+        assert_eq!(escape_json("a\r\nб"), "a\\r\\nб");
+    }
+}
@@ -1,166 +1,301 @@
-use std::time::Instant;
-
-pub struct GraphVisualization {
-    data: Vec<i32>,
-    markers: Vec<bool>,
-    visual_time: u128,
-    real_time: Instant,
-    compares: usize,
-    swaps: usize,
-    speed: f64,
-}
-
-impl GraphVisualization {
-    pub fn new(data: Vec<i32>) -> Self {
-        Self {
-            data,
-            markers: vec![false; 0], // будет инициализирован позже
-            visual_time: 0,
-            real_time: Instant::now(),
-            compares: 0,
-            swaps: 0,
-            speed: 1.0,
-        }
-    }
-
-    pub fn set_speed(&mut self, speed: f64) {
-        self.speed = speed;
-    }
-
-    pub fn update_data(&mut self, data: Vec<i32>) {
-        self.data = data;
-    }
-
-    pub fn set_markers(&mut self, markers: Vec<bool>) {
-        self.markers = markers;
-    }
-
-    pub fn increment_compares(&mut self) {
-        self.compares += 1;
-    }
-
-    pub fn increment_swaps(&mut self) {
-        self.swaps += 1;
-    }
-
-    pub fn update_visual_time(&mut self, millis: u128) {
-        self.visual_time = millis;
-    }
-
-    pub fn reset_stats(&mut self) {
-        self.compares = 0;
-        self.swaps = 0;
-        self.visual_time = 0;
-        self.real_time = Instant::now();
-    }
-
-    pub fn render(&self) {
-        // Очищаем экран (простой способ для терминала)
-        print!("{}[2J{}[H", 27 as char, 27 as char);
-
-        let max_height = self.data.iter().max().copied().unwrap_or(0);
-        let max_height = if max_height > 0 { max_height } else { 1 };
-        let width = self.data.len();
-
-        // Создаем сетку для визуализации
-        let mut grid: Vec<Vec<char>> = vec![vec![' '; width]; max_height as usize];
-
-        // Рисуем столбцы
-        for (i, &value) in self.data.iter().enumerate() {
-            let height = value as usize;
-            for j in 0..height {
-                let row = max_height as usize - 1 - j;
-                grid[row][i] = '█';
-            }
-        }
-
-        // Добавляем маркеры, если есть
-        if self.markers.len() == width {
-            for (i, &marked) in self.markers.iter().enumerate() {
-                if marked && max_height > 0 {
-                    let marker_row = max_height as usize - 1;
-                    if marker_row < grid.len() {
-                        grid[marker_row][i] = '-';
-                    }
-                }
-            }
-        }
-
-        // Выводим сетку
-        for row in grid {
-            println!("{}", row.iter().collect::<String>());
-        }
-
-        // Выводим разделительную линию
-        println!("{}", "-".repeat(width.max(40)));
-
-        // Выводим статистику
-        let real_elapsed = self.real_time.elapsed().as_millis();
-        print!("visual time: {}ms | real time: {}ms | compares: {} | swaps: {}",
-               self.visual_time,
-               real_elapsed,
-               self.compares,
-               self.swaps);
-
-        if self.speed != 1.0 {
-            print!(" | speed: {:.2}x", self.speed);
-        }
-        println!();
-
-        println!("{}", "-".repeat(width.max(40)));
-        println!();
-    }
-
-    pub fn render_step(&mut self, data: Vec<i32>, markers: Vec<bool>) {
-        self.update_data(data);
-        self.set_markers(markers);
-        self.render();
-        
-        // Искусственное замедление для наглядности
-        if self.speed > 0.0 && self.speed < 1.0 {
-            let delay = (100.0 / self.speed) as u64;
-            std::thread::sleep(std::time::Duration::from_millis(delay));
-        } else if self.speed > 1.0 {
-            let delay = (100.0 / self.speed) as u64;
-            std::thread::sleep(std::time::Duration::from_millis(delay));
-        }
-    }
-
-    pub fn final_render(&self) {
-        print!("{}[2J{}[H", 27 as char, 27 as char);
-        
-        let max_height = self.data.iter().max().copied().unwrap_or(0);
-        let max_height = if max_height > 0 { max_height } else { 1 };
-        let width = self.data.len();
-
-        let mut grid: Vec<Vec<char>> = vec![vec![' '; width]; max_height as usize];
-
-        for (i, &value) in self.data.iter().enumerate() {
-            let height = value as usize;
-            for j in 0..height {
-                let row = max_height as usize - 1 - j;
-                grid[row][i] = '█';
-            }
-        }
-
-        for row in grid {
-            println!("{}", row.iter().collect::<String>());
-        }
-
-        println!("{}", "-".repeat(width.max(40)));
-        let real_elapsed = self.real_time.elapsed().as_millis();
-        println!("FINAL: visual time: {}ms | real time: {}ms | compares: {} | swaps: {}",
-                 self.visual_time,
-                 real_elapsed,
-                 self.compares,
-                 self.swaps);
-        println!("{}", "-".repeat(width.max(40)));
-        println!("-> сортировка завершена!");
-    }
-}
-
-impl Default for GraphVisualization {
-    fn default() -> Self {
-        Self::new(vec![])
-    }
-}
+use super::highlight::colors_enabled;
+use super::{Highlight, RenderStats, Visualization, terminal_size};
+
+/// Строки под статистикой и приглашением, которые не занимают столбцы
+const RESERVED_ROWS: usize = 7;
+/// Части клетки снизу вверх, по восьмым долям
+const LOWER_BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Вертикальный масштаб диаграммы: диапазон значений, всегда включающий
+/// ноль, и число строк над нулевой линией и под ней
+#[derive(Debug, Clone, Copy)]
+pub struct ChartScale {
+    /// Строки всего, сверху вниз
+    pub rows: usize,
+    /// Строки над нулевой линией; остальные под ней
+    up_rows: usize,
+    /// Значение, соответствующее одной строке
+    unit: f64,
+}
+
+impl ChartScale {
+    /// Масштаб для значений не выше `max_rows` строк. Небольшие целые
+    /// значения занимают по строке на единицу, как и раньше
+    pub fn new(values: impl Iterator<Item = i32>, max_rows: usize) -> Self {
+        let (low, high) = values.fold((0i64, 0i64), |(low, high), v| (low.min(v as i64), high.max(v as i64)));
+        let span = (high - low) as usize;
+        let mut rows = span.clamp(1, max_rows.max(1));
+        let up_rows = if low == 0 {
+            rows
+        } else if high == 0 {
+            0
+        } else {
+            // Под положительные и отрицательные значения нужно хотя бы по строке
+            rows = rows.max(2);
+            ((rows as f64 * high as f64 / span as f64).round() as usize).clamp(1, rows - 1)
+        };
+        let down_rows = rows - up_rows;
+        let per_row = |extent: i64, rows: usize| if rows == 0 { 0.0 } else { extent as f64 / rows as f64 };
+        let unit = per_row(high, up_rows).max(per_row(-low, down_rows)).max(f64::MIN_POSITIVE);
+        Self { rows, up_rows, unit }
+    }
+
+    /// Высота столбца в восьмых долях строки; ненулевое значение всегда видно
+    fn eighths(&self, value: f64) -> usize {
+        let eighths = (value.abs() / self.unit * 8.0).round() as usize;
+        if value != 0.0 { eighths.max(1) } else { 0 }
+    }
+
+    /// Символ в строке `row` (сверху) для столбца со значением `value`
+    fn cell(&self, row: usize, value: f64, eighths: usize) -> char {
+        if row < self.up_rows {
+            if value <= 0.0 {
+                return ' ';
+            }
+            let level = self.up_rows - 1 - row;
+            LOWER_BLOCKS[eighths.saturating_sub(level * 8).min(8)]
+        } else {
+            if value >= 0.0 {
+                return ' ';
+            }
+            // Верхних блоков в Unicode только два, доли округляются до них
+            match eighths.saturating_sub((row - self.up_rows) * 8).min(8) {
+                0 => ' ',
+                1..=3 => '▔',
+                4..=7 => '▀',
+                _ => '█',
+            }
+        }
+    }
+}
+
+/// Сводит элементы в `columns` колонок: среднее значение и самая заметная
+/// роль среди элементов колонки
+fn bucket(data: &[i32], marks: &[Highlight], columns: usize) -> (Vec<f64>, Vec<Highlight>) {
+    let mark_at = |i: usize| marks.get(i).copied().unwrap_or_default();
+    if data.len() <= columns {
+        return (data.iter().map(|&v| v as f64).collect(), (0..data.len()).map(mark_at).collect());
+    }
+    (0..columns)
+        .map(|column| {
+            let range = column * data.len() / columns..(column + 1) * data.len() / columns;
+            let sum: f64 = data[range.clone()].iter().map(|&v| v as f64).sum();
+            let roles: Vec<Highlight> = range.clone().map(mark_at).collect();
+            (sum / range.len() as f64, merge_marks(&roles))
+        })
+        .unzip()
+}
+
+/// Текущая операция важнее всего; колонка зелёная, только если готова целиком
+fn merge_marks(marks: &[Highlight]) -> Highlight {
+    let priority = [Highlight::Swap, Highlight::Compare, Highlight::Pivot, Highlight::AuxWrite];
+    if let Some(&mark) = priority.iter().find(|mark| marks.contains(mark)) {
+        return mark;
+    }
+    if marks.iter().all(|&mark| mark == Highlight::Sorted) {
+        Highlight::Sorted
+    } else {
+        marks.iter().copied().find(|&mark| mark != Highlight::Sorted).unwrap_or_default()
+    }
+}
+
+pub struct GraphVisualization {
+    data: Vec<i32>,
+    marks: Vec<Highlight>,
+    aux: Vec<i32>,
+    aux_active: Option<usize>,
+    stats: RenderStats,
+}
+
+impl GraphVisualization {
+    pub fn new(data: Vec<i32>) -> Self {
+        Self {
+            data,
+            marks: Vec::new(), // будет инициализирован позже
+            aux: Vec::new(),
+            aux_active: None,
+            stats: RenderStats::default(),
+        }
+    }
+
+    /// Строки столбчатой диаграммы сверху вниз, не шире `columns` символов;
+    /// их же собирает в панели режим гонки. Если элементов больше, чем
+    /// колонок, соседние элементы сводятся в одну колонку со средним значением.
+    /// Столбцы окрашиваются по роли элемента, если `color` включён (обычно
+    /// [`colors_enabled`]); без цвета текущая операция отмечается дефисом сверху
+    pub fn bar_rows(data: &[i32], marks: &[Highlight], columns: usize, scale: &ChartScale, color: bool) -> Vec<String> {
+        let (values, marks) = bucket(data, marks, columns);
+        let width = values.len();
+
+        // Создаем сетку для визуализации
+        let mut grid: Vec<Vec<char>> = vec![vec![' '; width]; scale.rows];
+
+        // Рисуем столбцы: положительные растут вверх от нулевой линии,
+        // отрицательные вниз
+        for (i, &value) in values.iter().enumerate() {
+            let eighths = scale.eighths(value);
+            for (row, line) in grid.iter_mut().enumerate() {
+                line[i] = scale.cell(row, value, eighths);
+            }
+        }
+
+        // Добавляем маркеры, если есть
+        if !color {
+            for (i, mark) in marks.iter().enumerate() {
+                if mark.is_active() {
+                    grid[0][i] = '-';
+                }
+            }
+        }
+
+        if !color {
+            return grid.into_iter().map(|row| row.into_iter().collect()).collect();
+        }
+        grid.into_iter().map(|row| Self::paint_row(&row, &marks)).collect()
+    }
+
+    /// Собирает строку, окрашивая подряд идущие столбцы одной роли разом
+    fn paint_row(row: &[char], marks: &[Highlight]) -> String {
+        let mut line = String::new();
+        let mut start = 0;
+        while start < row.len() {
+            let mark = marks.get(start).copied().unwrap_or_default();
+            let mut end = start + 1;
+            while end < row.len() && marks.get(end).copied().unwrap_or_default() == mark {
+                end += 1;
+            }
+            let segment: String = row[start..end].iter().collect();
+            if segment.trim().is_empty() {
+                line.push_str(&segment);
+            } else {
+                line.push_str(&mark.paint(&segment));
+            }
+            start = end;
+        }
+        line
+    }
+
+    /// Ширина диаграммы и строки под столбцы основного массива и буфера
+    fn layout(&self) -> (usize, usize, usize) {
+        let (term_columns, term_rows) = terminal_size();
+        let columns = self.data.len().max(self.aux.len()).min(term_columns).max(1);
+        let free = term_rows.saturating_sub(RESERVED_ROWS).max(2);
+        if self.aux.is_empty() {
+            (columns, free, 0)
+        } else {
+            // Строка-разделитель и буфер в той же шкале, что и массив
+            let rows = free.saturating_sub(1).max(2);
+            (columns, rows - rows / 2, rows / 2)
+        }
+    }
+
+    /// Вспомогательный буфер рисуется под основным массивом в том же масштабе
+    fn draw_aux(&self, lines: &mut Vec<String>, columns: usize, rows: usize) {
+        if self.aux.is_empty() {
+            return;
+        }
+        lines.push("·".repeat(columns));
+        let mut marks = vec![Highlight::Aux; self.aux.len()];
+        if let Some(mark) = self.aux_active.and_then(|i| marks.get_mut(i)) {
+            *mark = Highlight::AuxWrite;
+        }
+        let aux_columns = columns.min(self.aux.len());
+        lines.extend(Self::bar_rows(&self.aux, &marks, aux_columns, &self.scale(rows), colors_enabled()));
+    }
+
+    fn scale(&self, rows: usize) -> ChartScale {
+        ChartScale::new(self.data.iter().chain(&self.aux).copied(), rows)
+    }
+}
+
+impl Visualization for GraphVisualization {
+    fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
+    fn stats_mut(&mut self) -> &mut RenderStats {
+        &mut self.stats
+    }
+
+    fn set_frame(&mut self, data: Vec<i32>, marks: Vec<Highlight>) {
+        self.data = data;
+        self.marks = marks;
+    }
+
+    fn set_aux(&mut self, aux: Vec<i32>, active: Option<usize>) {
+        self.aux = aux;
+        self.aux_active = active;
+    }
+
+    fn frame(&self) -> Vec<String> {
+        let (columns, rows, aux_rows) = self.layout();
+        let mut lines = Self::bar_rows(&self.data, &self.marks, columns, &self.scale(rows), colors_enabled());
+        self.draw_aux(&mut lines, columns, aux_rows);
+
+        // Разделительная линия и статистика
+        let separator = "-".repeat(columns.max(40));
+        let mut summary = self.stats.summary();
+        if self.stats.speed != 1.0 {
+            summary.push_str(&format!(" | speed: {:.2}x", self.stats.speed));
+        }
+        lines.extend([separator.clone(), summary, separator, String::new()]);
+        lines
+    }
+
+    fn final_frame(&self) -> Vec<String> {
+        // Отсортированный массив целиком на своих местах
+        let marks = vec![Highlight::Sorted; self.data.len()];
+        let (columns, rows, _) = self.layout();
+        let mut lines = Self::bar_rows(&self.data, &marks, columns, &self.scale(rows), colors_enabled());
+
+        let separator = "-".repeat(columns.max(40));
+        lines.extend([
+            separator.clone(),
+            format!("FINAL: {}", self.stats.final_summary()),
+            separator,
+            "-> сортировка завершена!".to_string(),
+        ]);
+        lines
+    }
+}
+
+impl Default for GraphVisualization {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(data: &[i32], columns: usize, max_rows: usize) -> Vec<String> {
+        let scale = ChartScale::new(data.iter().copied(), max_rows);
+        GraphVisualization::bar_rows(data, &[], columns, &scale, false)
+    }
+
+    #[test]
+    fn test_small_values_keep_one_row_per_unit() {
+        assert_eq!(rows(&[1, 3, 2], 80, 40), vec![" █ ", " ██", "███"]);
+    }
+
+    #[test]
+    fn test_large_values_are_scaled_to_rows() {
+        let rows = rows(&[10_000, 5_000], 80, 4);
+        assert_eq!(rows, vec!["█ ", "█ ", "██", "██"]);
+    }
+
+    #[test]
+    fn test_negative_values_grow_down_from_zero() {
+        assert_eq!(rows(&[2, -2], 80, 40), vec!["█ ", "█ ", " █", " █"]);
+    }
+
+    #[test]
+    fn test_wide_arrays_are_bucketed() {
+        let data: Vec<i32> = (1..=8).collect();
+        let rows = rows(&data, 4, 8);
+        assert!(rows.iter().all(|row| row.chars().count() == 4));
+        // Средние 1.5, 3.5, 5.5, 7.5: в верхней строке половинка блока
+        assert_eq!(rows[0], "   ▄");
+    }
+}
@@ -0,0 +1,126 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+use colored::Colorize;
+
+use crate::sort::SortEvent;
+
+/// Роль элемента в текущем кадре, от неё зависит цвет
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Highlight {
+    #[default]
+    None,
+    /// Сравниваемые элементы — жёлтый
+    Compare,
+    /// Обмен или запись — красный
+    Swap,
+    /// Опорный элемент — пурпурный
+    Pivot,
+    /// Элемент уже на своём месте — зелёный
+    Sorted,
+    /// Вспомогательный буфер — синий
+    Aux,
+    /// Последняя запись во вспомогательный буфер — ярко-синий
+    AuxWrite,
+}
+
+impl Highlight {
+    /// Роль элементов, которых касается событие
+    pub fn of(event: &SortEvent) -> Self {
+        match event {
+            SortEvent::Compare(..) => Highlight::Compare,
+            SortEvent::Swap(..) | SortEvent::Write(..) => Highlight::Swap,
+            SortEvent::Pivot(_) => Highlight::Pivot,
+            SortEvent::MarkSorted(_) => Highlight::Sorted,
+            SortEvent::AuxWrite(..) => Highlight::AuxWrite,
+            SortEvent::PhaseStart(_) => Highlight::None,
+        }
+    }
+
+    /// Текущая операция; без цвета такие элементы отмечаются дефисом
+    pub fn is_active(self) -> bool {
+        matches!(
+            self,
+            Highlight::Compare | Highlight::Swap | Highlight::Pivot | Highlight::AuxWrite
+        )
+    }
+
+    /// Цвет для экспорта в картинки, близкий к цвету в терминале
+    pub fn rgb(self) -> [u8; 3] {
+        match self {
+            Highlight::None => [200, 200, 200],
+            Highlight::Compare => [240, 200, 0],
+            Highlight::Swap => [224, 60, 60],
+            Highlight::Pivot => [200, 80, 200],
+            Highlight::Sorted => [80, 200, 80],
+            Highlight::Aux => [60, 120, 224],
+            Highlight::AuxWrite => [120, 180, 255],
+        }
+    }
+
+    /// Раскрашивает текст; в монохромном режиме возвращает его без изменений
+    pub fn paint(self, text: &str) -> String {
+        match self {
+            Highlight::None => text.to_string(),
+            Highlight::Compare => text.yellow().to_string(),
+            Highlight::Swap => text.red().to_string(),
+            Highlight::Pivot => text.magenta().to_string(),
+            Highlight::Sorted => text.green().to_string(),
+            Highlight::Aux => text.blue().to_string(),
+            Highlight::AuxWrite => text.bright_blue().bold().to_string(),
+        }
+    }
+}
+
+/// Включает цвета, только если вывод идёт в терминал и не задан `NO_COLOR`
+pub fn init_colors() {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    colored::control::set_override(io::stdout().is_terminal() && !no_color);
+}
+
+pub fn colors_enabled() -> bool {
+    colored::control::SHOULD_COLORIZE.should_colorize()
+}
+
+/// Роли элементов кадра: готовые позиции и поверх них элементы события
+pub fn frame_highlights(sorted: &[bool], event: Option<&SortEvent>) -> Vec<Highlight> {
+    let mut marks: Vec<Highlight> = sorted
+        .iter()
+        .map(|&done| if done { Highlight::Sorted } else { Highlight::None })
+        .collect();
+    if let Some(event) = event {
+        let role = Highlight::of(event);
+        for i in event.indices() {
+            if let Some(mark) = marks.get_mut(i) {
+                *mark = role;
+            }
+        }
+    }
+    marks
+}
+
+/// Запоминает позиции из `MarkSorted`; `len` — длина основного массива
+pub fn record_sorted(sorted: &mut Vec<bool>, len: usize, event: &SortEvent) {
+    if let SortEvent::MarkSorted(range) = event {
+        sorted.resize(len, false);
+        for done in &mut sorted[range.clone()] {
+            *done = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_roles_override_sorted_positions() {
+        let mut sorted = vec![false; 4];
+        record_sorted(&mut sorted, 4, &SortEvent::MarkSorted(2..4));
+        let marks = frame_highlights(&sorted, Some(&SortEvent::Swap(1, 3)));
+        assert_eq!(
+            marks,
+            vec![Highlight::None, Highlight::Swap, Highlight::Sorted, Highlight::Swap]
+        );
+    }
+}
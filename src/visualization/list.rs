@@ -1,144 +1,131 @@
-use std::time::Instant;
-
-pub struct ListVisualization {
-    data: Vec<i32>,
-    markers: Vec<bool>,
-    visual_time: u128,
-    real_time: Instant,
-    compares: usize,
-    swaps: usize,
-    speed: f64,
-}
-
-impl ListVisualization {
-    pub fn new(data: Vec<i32>) -> Self {
-        Self {
-            data,
-            markers: vec![false; 0],
-            visual_time: 0,
-            real_time: Instant::now(),
-            compares: 0,
-            swaps: 0,
-            speed: 1.0,
-        }
-    }
-
-    pub fn set_speed(&mut self, speed: f64) {
-        self.speed = speed;
-    }
-
-    pub fn update_data(&mut self, data: Vec<i32>) {
-        self.data = data;
-    }
-
-    pub fn set_markers(&mut self, markers: Vec<bool>) {
-        self.markers = markers;
-    }
-
-    pub fn increment_compares(&mut self) {
-        self.compares += 1;
-    }
-
-    pub fn increment_swaps(&mut self) {
-        self.swaps += 1;
-    }
-
-    pub fn update_visual_time(&mut self, millis: u128) {
-        self.visual_time = millis;
-    }
-
-    pub fn reset_stats(&mut self) {
-        self.compares = 0;
-        self.swaps = 0;
-        self.visual_time = 0;
-        self.real_time = Instant::now();
-    }
-
-    pub fn render(&self) {
-        // Очищаем экран
-        print!("{}[2J{}[H", 27 as char, 27 as char);
-
-        // Выводим массив
-        print!("[");
-        for (i, &value) in self.data.iter().enumerate() {
-            if i > 0 {
-                print!(", ");
-            }
-            print!("{}", value);
-        }
-        println!("]");
-
-        // Выводим маркеры
-        if !self.markers.is_empty() && self.markers.len() == self.data.len() {
-            print!(" ");
-            for (i, &marked) in self.markers.iter().enumerate() {
-                if i > 0 {
-                    print!("  ");
-                }
-                if marked {
-                    print!("-");
-                } else {
-                    print!(" ");
-                }
-            }
-            println!();
-        }
-
-        // Выводим статистику
-        println!("{}", "-".repeat(40));
-        let real_elapsed = self.real_time.elapsed().as_millis();
-        print!("visual time: {}ms | real time: {}ms | compares: {} | swaps: {}",
-               self.visual_time,
-               real_elapsed,
-               self.compares,
-               self.swaps);
-        if self.speed != 1.0 {
-            print!(" | speed: {:.2}x", self.speed);
-        }
-        println!();
-        println!("{}", "-".repeat(40));
-        println!("-> ");
-    }
-
-    pub fn render_step(&mut self, data: Vec<i32>, markers: Vec<bool>) {
-        self.update_data(data);
-        self.set_markers(markers);
-        self.render();
-        
-        // Искусственное замедление
-        if self.speed > 0.0 && self.speed < 1.0 {
-            let delay = (100.0 / self.speed) as u64;
-            std::thread::sleep(std::time::Duration::from_millis(delay));
-        } else if self.speed > 1.0 {
-            let delay = (100.0 / self.speed) as u64;
-            std::thread::sleep(std::time::Duration::from_millis(delay));
-        }
-    }
-
-    pub fn final_render(&self) {
-        print!("{}[2J{}[H", 27 as char, 27 as char);
-        print!("[");
-        for (i, &value) in self.data.iter().enumerate() {
-            if i > 0 {
-                print!(", ");
-            }
-            print!("{}", value);
-        }
-        println!("]");
-        println!("{}", "-".repeat(40));
-        let real_elapsed = self.real_time.elapsed().as_millis();
-        println!("FINAL: visual time: {}ms | real time: {}ms | compares: {} | swaps: {}",
-                 self.visual_time,
-                 real_elapsed,
-                 self.compares,
-                 self.swaps);
-        println!("{}", "-".repeat(40));
-        println!("-> сортировка завершена!");
-    }
-}
-
-impl Default for ListVisualization {
-    fn default() -> Self {
-        Self::new(vec![])
-    }
-}
+use super::highlight::colors_enabled;
+use super::{Highlight, RenderStats, Visualization};
+use crate::sort::Tagged;
+
+pub struct ListVisualization {
+    data: Vec<i32>,
+    marks: Vec<Highlight>,
+    aux: Vec<i32>,
+    aux_active: Option<usize>,
+    tags: Option<Vec<usize>>,
+    stats: RenderStats,
+}
+
+impl ListVisualization {
+    pub fn new(data: Vec<i32>) -> Self {
+        Self {
+            data,
+            marks: Vec::new(),
+            aux: Vec::new(),
+            aux_active: None,
+            tags: None,
+            stats: RenderStats::default(),
+        }
+    }
+
+    fn array_line(&self, marks: &[Highlight]) -> String {
+        let mut line = String::from("[");
+        for (i, &value) in self.data.iter().enumerate() {
+            if i > 0 {
+                line.push_str(", ");
+            }
+            let mut text = value.to_string();
+            // В режиме проверки стабильности рядом с ключом печатается метка: 5a 5b 3a
+            if let Some(&tag) = self.tags.as_ref().and_then(|tags| tags.get(i)) {
+                text.push_str(&Tagged::tag_label(tag));
+            }
+            line.push_str(&marks.get(i).copied().unwrap_or_default().paint(&text));
+        }
+        line.push(']');
+        line
+    }
+
+    /// Вспомогательный буфер; последняя запись выделена цветом, а без
+    /// цвета — скобками
+    fn aux_line(&self) -> Option<String> {
+        if self.aux.is_empty() {
+            return None;
+        }
+        let mut line = String::from("aux: [");
+        for (i, &value) in self.aux.iter().enumerate() {
+            if i > 0 {
+                line.push_str(", ");
+            }
+            if self.aux_active != Some(i) {
+                line.push_str(&Highlight::Aux.paint(&value.to_string()));
+            } else if colors_enabled() {
+                line.push_str(&Highlight::AuxWrite.paint(&value.to_string()));
+            } else {
+                line.push_str(&format!("<{}>", value));
+            }
+        }
+        line.push(']');
+        Some(line)
+    }
+}
+
+impl Visualization for ListVisualization {
+    fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
+    fn stats_mut(&mut self) -> &mut RenderStats {
+        &mut self.stats
+    }
+
+    fn set_frame(&mut self, data: Vec<i32>, marks: Vec<Highlight>) {
+        self.data = data;
+        self.marks = marks;
+    }
+
+    fn set_aux(&mut self, aux: Vec<i32>, active: Option<usize>) {
+        self.aux = aux;
+        self.aux_active = active;
+    }
+
+    fn set_tags(&mut self, tags: Option<Vec<usize>>) {
+        self.tags = tags;
+    }
+
+    fn frame(&self) -> Vec<String> {
+        // Выводим массив
+        let mut lines = vec![self.array_line(&self.marks)];
+
+        // Без цвета текущая операция отмечается строкой маркеров
+        if !colors_enabled() && self.marks.iter().any(|mark| mark.is_active()) && self.marks.len() == self.data.len() {
+            let mut markers = String::from(" ");
+            for (i, mark) in self.marks.iter().enumerate() {
+                if i > 0 {
+                    markers.push_str("  ");
+                }
+                markers.push(if mark.is_active() { '-' } else { ' ' });
+            }
+            lines.push(markers);
+        }
+        lines.extend(self.aux_line());
+
+        // Выводим статистику
+        let mut summary = self.stats.summary();
+        if self.stats.speed != 1.0 {
+            summary.push_str(&format!(" | speed: {:.2}x", self.stats.speed));
+        }
+        lines.extend(["-".repeat(40), summary, "-".repeat(40), "-> ".to_string()]);
+        lines
+    }
+
+    fn final_frame(&self) -> Vec<String> {
+        vec![
+            self.array_line(&vec![Highlight::Sorted; self.data.len()]),
+            "-".repeat(40),
+            format!("FINAL: {}", self.stats.final_summary()),
+            "-".repeat(40),
+            "-> сортировка завершена!".to_string(),
+        ]
+    }
+}
+
+impl Default for ListVisualization {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
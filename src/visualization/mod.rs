@@ -1,3 +1,267 @@
-pub mod graphs;
-pub mod list;
-
+pub mod cast;
+pub mod graphs;
+pub mod highlight;
+pub mod list;
+pub mod network;
+pub mod player;
+pub mod race;
+pub mod screen;
+
+pub use graphs::GraphVisualization;
+pub use highlight::{Highlight, init_colors};
+pub use list::ListVisualization;
+pub use network::NetworkVisualization;
+pub use player::Player;
+pub use race::RaceView;
+
+use std::time::Instant;
+
+use crate::sort::{Counters, Network, SortEvent, Tagged, Trace};
+use highlight::{frame_highlights, record_sorted};
+
+/// Размер терминала в колонках и строках; если вывод не в терминал или
+/// размер неизвестен, используются стандартные 80×24
+pub fn terminal_size() -> (usize, usize) {
+    match crossterm::terminal::size() {
+        Ok((columns, rows)) if columns > 0 && rows > 0 => (columns as usize, rows as usize),
+        _ => (80, 24),
+    }
+}
+
+/// Счётчики и время, общие для всех визуализаций
+pub struct RenderStats {
+    pub visual_time: u128,
+    pub real_time: Instant,
+    pub counters: Counters,
+    pub speed: f64,
+    pub phase: Option<String>,
+    /// Позиции, которые алгоритм отметил как окончательные
+    pub sorted: Vec<bool>,
+    /// Сеть сравнения, если алгоритм — сеть; сохраняется при сбросе счётчиков
+    pub network: Option<Network>,
+}
+
+impl Default for RenderStats {
+    fn default() -> Self {
+        Self {
+            visual_time: 0,
+            real_time: Instant::now(),
+            counters: Counters::default(),
+            speed: 1.0,
+            phase: None,
+            sorted: Vec::new(),
+            network: None,
+        }
+    }
+}
+
+impl RenderStats {
+    /// Строка статистики, которую визуализации выводят под массивом.
+    /// Во время проигрывания показываются только счётчики, известные по событиям.
+    pub fn summary(&self) -> String {
+        let counters = &self.counters;
+        let mut summary = format!(
+            "visual time: {}ms | real time: {}ms | compares: {} | swaps: {} | writes: {} | aux writes: {}",
+            self.visual_time,
+            self.real_time.elapsed().as_millis(),
+            counters.compares,
+            counters.swaps,
+            counters.writes,
+            counters.aux_writes
+        );
+        summary.push_str(&self.network_summary());
+        if let Some(phase) = &self.phase {
+            summary.push_str(&format!(" | phase: {}", phase));
+        }
+        summary
+    }
+
+    /// Итоговая строка со всеми счётчиками, включая чтения и пик доп. памяти
+    pub fn final_summary(&self) -> String {
+        format!(
+            "visual time: {}ms | real time: {}ms | {}{}",
+            self.visual_time,
+            self.real_time.elapsed().as_millis(),
+            self.counters,
+            self.network_summary()
+        )
+    }
+
+    /// Глубина и число компараторов сети, пусто для обычных алгоритмов
+    fn network_summary(&self) -> String {
+        match &self.network {
+            Some(network) => format!(
+                " | network depth: {} | comparators: {}",
+                network.depth(),
+                network.comparators()
+            ),
+            None => String::new(),
+        }
+    }
+}
+
+/// Общий интерфейс для всех способов отображения сортировки.
+///
+/// Реализации хранят текущий кадр и счётчики, а разбор событий сортировки,
+/// отрисовку шагов и задержку получают от методов по умолчанию.
+pub trait Visualization {
+    fn stats(&self) -> &RenderStats;
+
+    fn stats_mut(&mut self) -> &mut RenderStats;
+
+    /// Запоминает новый кадр: массив и роль каждого элемента
+    fn set_frame(&mut self, data: Vec<i32>, marks: Vec<Highlight>);
+
+    /// Запоминает вспомогательный буфер алгоритма и последнюю запись в него
+    fn set_aux(&mut self, aux: Vec<i32>, active: Option<usize>);
+
+    /// Запоминает метки одинаковых ключей (`a`, `b`, ...) для каждой позиции;
+    /// визуализации, которые их не показывают, могут не переопределять метод
+    fn set_tags(&mut self, _tags: Option<Vec<usize>>) {}
+
+    /// Строки текущего кадра вместе со статистикой
+    fn frame(&self) -> Vec<String>;
+
+    /// Строки итогового кадра после завершения сортировки
+    fn final_frame(&self) -> Vec<String>;
+
+    /// Рисует текущий кадр; на экране меняется только то, что изменилось
+    fn render(&self) {
+        screen::draw(&self.frame());
+    }
+
+    /// Рисует итоговый кадр и возвращает терминал к обычному выводу
+    fn final_render(&self) {
+        screen::draw(&self.final_frame());
+        screen::release();
+    }
+
+    fn set_speed(&mut self, speed: f64) {
+        self.stats_mut().speed = speed;
+    }
+
+    fn update_visual_time(&mut self, millis: u128) {
+        self.stats_mut().visual_time = millis;
+    }
+
+    fn reset_stats(&mut self) {
+        let speed = self.stats().speed;
+        let network = self.stats_mut().network.take();
+        *self.stats_mut() = RenderStats {
+            speed,
+            network,
+            ..RenderStats::default()
+        };
+    }
+
+    fn render_step(&mut self, data: Vec<i32>, marks: Vec<Highlight>) {
+        self.set_frame(data, marks);
+        self.render();
+
+        // Искусственное замедление для наглядности
+        let speed = self.stats().speed;
+        if speed > 0.0 && speed != 1.0 {
+            let delay = (100.0 / speed) as u64;
+            std::thread::sleep(std::time::Duration::from_millis(delay));
+        }
+    }
+
+    /// Обрабатывает одно событие сортировки; `data` и `aux` уже содержат его результат
+    fn on_event(&mut self, data: &[i32], aux: &[i32], event: &SortEvent) {
+        self.stats_mut().counters.record(event);
+        match event {
+            SortEvent::PhaseStart(name) => {
+                self.stats_mut().phase = Some(name.clone());
+                return;
+            }
+            SortEvent::AuxWrite(i, _) => {
+                self.set_aux(aux.to_vec(), Some(*i));
+                let marks = frame_highlights(&self.stats().sorted, None);
+                self.render_step(data.to_vec(), marks);
+                return;
+            }
+            // Готовые участки подсвечиваются со следующего кадра
+            SortEvent::MarkSorted(_) => {
+                record_sorted(&mut self.stats_mut().sorted, data.len(), event);
+                return;
+            }
+            SortEvent::Compare(..) | SortEvent::Swap(..) | SortEvent::Write(..) | SortEvent::Pivot(_) => {}
+        }
+        let marks = frame_highlights(&self.stats().sorted, Some(event));
+        self.render_step(data.to_vec(), marks);
+    }
+
+    /// Проигрывает записанную сортировку с начального массива
+    fn play(&mut self, trace: &Trace) {
+        let mut data = trace.initial.clone();
+        let mut aux = Vec::new();
+        self.set_tags(None);
+        self.stats_mut().sorted = vec![false; data.len()];
+        self.set_frame(data.clone(), Vec::new());
+        self.set_aux(Vec::new(), None);
+        for event in &trace.events {
+            event.apply(&mut data, &mut aux);
+            self.on_event(&data, &aux, event);
+        }
+        // Чтения и доп. память известны только записавшему трассу
+        self.stats_mut().counters = trace.counters;
+    }
+
+    /// Проигрывает сортировку записей с метками: рисуются ключи, а метки
+    /// передаются через `set_tags` перед каждым кадром
+    fn play_tagged(&mut self, trace: &Trace<Tagged>) {
+        let mut records = trace.initial.clone();
+        let mut aux = Vec::new();
+        let keys = |records: &[Tagged]| records.iter().map(|r| r.key).collect::<Vec<i32>>();
+        let tags = |records: &[Tagged]| records.iter().map(|r| r.tag).collect::<Vec<usize>>();
+
+        self.set_tags(Some(tags(&records)));
+        self.stats_mut().sorted = vec![false; records.len()];
+        self.set_frame(keys(&records), Vec::new());
+        self.set_aux(Vec::new(), None);
+        for event in &trace.events {
+            event.apply(&mut records, &mut aux);
+            self.set_tags(Some(tags(&records)));
+            self.on_event(&keys(&records), &keys(&aux), &event.map(|r| r.key));
+        }
+        self.stats_mut().counters = trace.counters;
+    }
+}
+
+/// Способ отображения, который включается командой `.<name>`.
+///
+/// Разбор команд, справка и переключение режима берут визуализации отсюда,
+/// поэтому для нового способа отображения достаточно добавить запись
+/// в [`RENDERERS`].
+#[derive(Debug)]
+pub struct Renderer {
+    /// Имя в нижнем регистре, оно же команда `.name`
+    pub name: &'static str,
+    /// Описание для справки и сообщения о смене режима
+    pub description: &'static str,
+    pub create: fn() -> Box<dyn Visualization>,
+}
+
+/// Все визуализации; первая используется по умолчанию
+pub static RENDERERS: &[Renderer] = &[
+    Renderer {
+        name: "list",
+        description: "список",
+        create: || Box::new(ListVisualization::default()),
+    },
+    Renderer {
+        name: "graphs",
+        description: "столбцы",
+        create: || Box::new(GraphVisualization::default()),
+    },
+    Renderer {
+        name: "network",
+        description: "схема сети сравнения (bitonic, oddeven; у остальных — текущая пара)",
+        create: || Box::new(NetworkVisualization::default()),
+    },
+];
+
+/// Ищет визуализацию по имени без учёта регистра
+pub fn find(name: &str) -> Option<&'static Renderer> {
+    RENDERERS.iter().find(|renderer| renderer.name.eq_ignore_ascii_case(name))
+}
@@ -0,0 +1,210 @@
+use crate::sort::Network;
+
+use super::highlight::colors_enabled;
+use super::{Highlight, RenderStats, Visualization, terminal_size};
+
+/// Строки над проводами и под ними: маркер шага, статистика, приглашение
+const RESERVED_ROWS: usize = 6;
+
+/// Компаратор на схеме: колонка и соединённые им провода
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Placed {
+    column: usize,
+    low: usize,
+    high: usize,
+}
+
+/// Раскладывает компараторы по колонкам: слой занимает столько колонок,
+/// сколько нужно, чтобы отрезки в одной колонке не пересекались и не
+/// соприкасались (иначе два компаратора слились бы в один), а между слоями
+/// остаётся пустая колонка.
+/// Возвращает компараторы в порядке выполнения и общую ширину схемы.
+fn place(network: &Network) -> (Vec<Placed>, usize) {
+    let mut placed = Vec::with_capacity(network.comparators());
+    let mut start = 1;
+    for layer in &network.layers {
+        let mut lanes: Vec<Vec<(usize, usize)>> = Vec::new();
+        for &(low, high) in &layer.comparators {
+            let free = lanes
+                .iter()
+                .position(|spans| spans.iter().all(|&(a, b)| high + 1 < a || b + 1 < low));
+            let lane = free.unwrap_or_else(|| {
+                lanes.push(Vec::new());
+                lanes.len() - 1
+            });
+            lanes[lane].push((low, high));
+            placed.push(Placed { column: start + lane, low, high });
+        }
+        start += lanes.len() + 1;
+    }
+    (placed, start)
+}
+
+/// Схема сети сравнения: провода — позиции массива, компараторы — вертикальные
+/// отрезки между ними, слева текущие значения на проводах.
+///
+/// Текущий компаратор отмечен стрелкой сверху и окрашен по роли шага.
+/// У алгоритмов без сети рисуется только пара, которую сравнивают сейчас.
+pub struct NetworkVisualization {
+    data: Vec<i32>,
+    marks: Vec<Highlight>,
+    stats: RenderStats,
+}
+
+impl NetworkVisualization {
+    pub fn new(data: Vec<i32>) -> Self {
+        Self {
+            data,
+            marks: Vec::new(),
+            stats: RenderStats::default(),
+        }
+    }
+
+    /// Компараторы схемы, индекс текущего из них и ширина схемы
+    fn layout(&self, done: bool) -> (Vec<Placed>, Option<usize>, usize) {
+        if let Some(network) = &self.stats.network {
+            let (placed, width) = place(network);
+            // Сеть сравнивает каждую пару ровно один раз и по порядку
+            let current = self.stats.counters.compares.checked_sub(1).filter(|&i| !done && i < placed.len());
+            return (placed, current, width);
+        }
+        let mut active = self.marks.iter().enumerate().filter(|(_, mark)| mark.is_active()).map(|(i, _)| i);
+        match (active.next(), active.next()) {
+            (Some(low), Some(high)) if !done => (vec![Placed { column: 1, low, high }], Some(0), 3),
+            _ => (Vec::new(), None, 3),
+        }
+    }
+
+    /// Провода с компараторами, не шире `columns` символов; если схема не
+    /// помещается, показывается её часть вокруг текущего компаратора.
+    /// Роли окрашиваются, только если включён `color`
+    fn wires(&self, marks: &[Highlight], columns: usize, done: bool, color: bool) -> Vec<String> {
+        let paint = |role: Highlight, text: &str| if color { role.paint(text) } else { text.to_string() };
+        let (placed, current, width) = self.layout(done);
+        let label_width = self.data.iter().map(|v| v.to_string().len()).max().unwrap_or(1);
+
+        let mut grid = vec![vec![('─', Highlight::None); width]; self.data.len()];
+        for (index, comparator) in placed.iter().enumerate() {
+            let role = if Some(index) == current {
+                marks.get(comparator.low).copied().filter(|mark| mark.is_active()).unwrap_or(Highlight::Compare)
+            } else {
+                Highlight::None
+            };
+            grid[comparator.low][comparator.column] = ('●', role);
+            grid[comparator.high][comparator.column] = ('●', role);
+            for row in &mut grid[comparator.low + 1..comparator.high] {
+                row[comparator.column] = ('┼', role);
+            }
+        }
+
+        let visible = columns.saturating_sub(label_width + 1).max(1).min(width);
+        let current_column = current.map(|i| placed[i].column);
+        let offset = current_column.map_or(0, |column| column.saturating_sub(visible / 2).min(width - visible));
+
+        let mut header = " ".repeat(label_width + 1);
+        for column in offset..offset + visible {
+            header.push(if Some(column) == current_column { '▼' } else { ' ' });
+        }
+        let mut lines = vec![header];
+        for (wire, row) in grid.iter().enumerate() {
+            let mark = marks.get(wire).copied().unwrap_or_default();
+            let mut line = paint(mark, &format!("{:>label_width$}", self.data[wire]));
+            line.push(' ');
+            let mut start = offset;
+            while start < offset + visible {
+                let role = row[start].1;
+                let mut end = start + 1;
+                while end < offset + visible && row[end].1 == role {
+                    end += 1;
+                }
+                let segment: String = row[start..end].iter().map(|&(ch, _)| ch).collect();
+                line.push_str(&paint(role, &segment));
+                start = end;
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Схема или сообщение, если провода не помещаются по высоте
+    fn diagram(&self, marks: &[Highlight], done: bool) -> Vec<String> {
+        let (columns, rows) = terminal_size();
+        if self.data.len() + RESERVED_ROWS > rows {
+            return vec![format!(
+                "сеть из {} проводов не помещается в терминал ({} строк), уменьшите массив",
+                self.data.len(),
+                rows
+            )];
+        }
+        self.wires(marks, columns, done, colors_enabled())
+    }
+}
+
+impl Visualization for NetworkVisualization {
+    fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
+    fn stats_mut(&mut self) -> &mut RenderStats {
+        &mut self.stats
+    }
+
+    fn set_frame(&mut self, data: Vec<i32>, marks: Vec<Highlight>) {
+        self.data = data;
+        self.marks = marks;
+    }
+
+    // Сети не пользуются вспомогательной памятью, буфер других алгоритмов не рисуется
+    fn set_aux(&mut self, _aux: Vec<i32>, _active: Option<usize>) {}
+
+    fn frame(&self) -> Vec<String> {
+        let mut lines = self.diagram(&self.marks, false);
+        let mut summary = self.stats.summary();
+        if self.stats.speed != 1.0 {
+            summary.push_str(&format!(" | speed: {:.2}x", self.stats.speed));
+        }
+        lines.extend(["-".repeat(40), summary, "-".repeat(40), String::new()]);
+        lines
+    }
+
+    fn final_frame(&self) -> Vec<String> {
+        let mut lines = self.diagram(&vec![Highlight::Sorted; self.data.len()], true);
+        lines.extend([
+            "-".repeat(40),
+            format!("FINAL: {}", self.stats.final_summary()),
+            "-".repeat(40),
+            "-> сортировка завершена!".to_string(),
+        ]);
+        lines
+    }
+}
+
+impl Default for NetworkVisualization {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlapping_comparators_get_separate_columns() {
+        let mut viz = NetworkVisualization::new(vec![3, 1, 2, 0]);
+        viz.stats.network = Some(Network::bitonic(4));
+        viz.stats.counters.compares = 1;
+        // Слои (0,1)(2,3), затем вложенные (0,3)(1,2), затем снова (0,1)(2,3):
+        // соседние и вложенные компараторы стоят в разных колонках
+        assert_eq!(
+            viz.wires(&[], 80, false, false),
+            vec![
+                "   ▼        ",
+                "3 ─●──●──●──",
+                "1 ─●──┼●─●──",
+                "2 ──●─┼●──●─",
+                "0 ──●─●───●─",
+            ]
+        );
+    }
+}
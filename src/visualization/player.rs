@@ -0,0 +1,238 @@
+use std::io;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+
+use super::{Visualization, screen};
+use super::highlight::{frame_highlights, record_sorted};
+use crate::sort::{Counters, SortEvent, Trace};
+
+/// Через сколько событий сохраняется снимок состояния для перемотки
+const CHECKPOINT_EVERY: usize = 256;
+const MIN_SPEED: f64 = 0.125;
+const MAX_SPEED: f64 = 64.0;
+
+const CONTROLS: &str =
+    "[пробел] пауза  [←/→] шаг  [n/p] фаза  [+/-] скорость  [0-9] перемотка  [r] в начало  [q] выход";
+
+/// Состояние проигрывания после некоторого числа событий
+#[derive(Clone)]
+struct Snapshot {
+    data: Vec<i32>,
+    aux: Vec<i32>,
+    counters: Counters,
+    phase: Option<String>,
+    sorted: Vec<bool>,
+}
+
+impl Snapshot {
+    fn apply(&mut self, event: &SortEvent) {
+        event.apply(&mut self.data, &mut self.aux);
+        self.counters.record(event);
+        record_sorted(&mut self.sorted, self.data.len(), event);
+        if let SortEvent::PhaseStart(name) = event {
+            self.phase = Some(name.clone());
+        }
+    }
+}
+
+/// Интерактивный просмотр записанной сортировки: пауза, шаги вперёд и
+/// назад, переход между фазами, скорость и перемотка к любому шагу.
+///
+/// Кадры рисует текущая визуализация; любое состояние восстанавливается
+/// от ближайшего снимка, поэтому назад можно идти так же быстро, как вперёд.
+pub struct Player<'a> {
+    trace: &'a Trace,
+    checkpoints: Vec<Snapshot>,
+    /// Индексы событий `PhaseStart`
+    phase_starts: Vec<usize>,
+    /// Число уже применённых событий
+    position: usize,
+    paused: bool,
+    speed: f64,
+}
+
+impl<'a> Player<'a> {
+    pub fn new(trace: &'a Trace, speed: f64) -> Self {
+        let mut state = Snapshot {
+            data: trace.initial.clone(),
+            aux: Vec::new(),
+            counters: Counters::default(),
+            phase: None,
+            sorted: vec![false; trace.initial.len()],
+        };
+        let mut checkpoints = vec![state.clone()];
+        for (i, event) in trace.events.iter().enumerate() {
+            state.apply(event);
+            if (i + 1) % CHECKPOINT_EVERY == 0 {
+                checkpoints.push(state.clone());
+            }
+        }
+        let phase_starts = trace
+            .events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| matches!(event, SortEvent::PhaseStart(_)))
+            .map(|(i, _)| i)
+            .collect();
+
+        Self {
+            trace,
+            checkpoints,
+            phase_starts,
+            position: 0,
+            paused: false,
+            speed: if speed > 0.0 { speed } else { 1.0 },
+        }
+    }
+
+    /// Проигрывает трассу, пока пользователь не выйдет; в конце трассы
+    /// проигрывание останавливается, но перематывать можно дальше
+    pub fn run(&mut self, viz: &mut dyn Visualization) -> io::Result<()> {
+        let result = self.play_loop(viz);
+        screen::release();
+        result
+    }
+
+    fn play_loop(&mut self, viz: &mut dyn Visualization) -> io::Result<()> {
+        loop {
+            self.render(viz);
+            let at_end = self.position == self.trace.events.len();
+            let timeout = if self.paused || at_end {
+                None
+            } else {
+                Some(Duration::from_millis((100.0 / self.speed) as u64))
+            };
+
+            match read_input(timeout)? {
+                Input::Timeout => self.advance_visible(),
+                Input::Key(key) if !self.handle_key(key) => return Ok(()),
+                Input::Key(_) | Input::Redraw => {}
+            }
+        }
+    }
+
+    /// Обрабатывает клавишу; `false` означает выход из просмотра
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let len = self.trace.events.len();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Right => {
+                self.paused = true;
+                self.position = (self.position + 1).min(len);
+            }
+            KeyCode::Left => {
+                self.paused = true;
+                self.position = self.position.saturating_sub(1);
+            }
+            KeyCode::Char('n') | KeyCode::PageDown => {
+                // Сразу после события PhaseStart, чтобы название фазы было видно
+                let next = self.phase_starts.iter().find(|&&start| start >= self.position);
+                self.position = next.map_or(len, |start| start + 1);
+            }
+            KeyCode::Char('p') | KeyCode::PageUp => {
+                let previous = self
+                    .phase_starts
+                    .iter()
+                    .rev()
+                    .find(|&&start| start + 1 < self.position);
+                self.position = previous.map_or(0, |start| start + 1);
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => self.speed = (self.speed * 2.0).min(MAX_SPEED),
+            KeyCode::Char('-') => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+            KeyCode::Char('r') | KeyCode::Home => self.position = 0,
+            KeyCode::End => self.position = len,
+            KeyCode::Char(digit @ '0'..='9') => {
+                let tenth = digit.to_digit(10).unwrap_or(0) as usize;
+                self.position = len * tenth / 10;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Шаг проигрывания: одно событие, плюс события без отрисовки
+    /// (смена фазы, готовые участки), чтобы не тратить на них кадры
+    fn advance_visible(&mut self) {
+        let events = &self.trace.events;
+        self.position += 1;
+        while self.position < events.len()
+            && matches!(events[self.position - 1], SortEvent::PhaseStart(_) | SortEvent::MarkSorted(_))
+        {
+            self.position += 1;
+        }
+    }
+
+    fn state_at(&self, position: usize) -> Snapshot {
+        let index = position / CHECKPOINT_EVERY;
+        let mut state = self.checkpoints[index].clone();
+        for event in &self.trace.events[index * CHECKPOINT_EVERY..position] {
+            state.apply(event);
+        }
+        state
+    }
+
+    fn render(&self, viz: &mut dyn Visualization) {
+        let state = self.state_at(self.position);
+        let last = self.position.checked_sub(1).map(|i| &self.trace.events[i]);
+        let marks = frame_highlights(&state.sorted, last);
+        let aux_active = match last {
+            Some(SortEvent::AuxWrite(i, _)) => Some(*i),
+            _ => None,
+        };
+
+        let stats = viz.stats_mut();
+        stats.speed = self.speed;
+        stats.counters = state.counters;
+        stats.phase = state.phase;
+        viz.set_aux(state.aux, aux_active);
+        viz.set_frame(state.data, marks);
+        let mut lines = viz.frame();
+
+        let status = if self.position == self.trace.events.len() {
+            "конец"
+        } else if self.paused {
+            "пауза"
+        } else {
+            "воспроизведение"
+        };
+        lines.push(format!("{}: шаг {}/{}", status, self.position, self.trace.events.len()));
+        lines.push(CONTROLS.to_string());
+        screen::draw(&lines);
+    }
+}
+
+/// Чем закончилось ожидание ввода
+enum Input {
+    Key(KeyEvent),
+    /// Время кадра вышло, пора сделать шаг
+    Timeout,
+    /// Прочие события (изменение размера и т. п.): кадр только перерисовывается
+    Redraw,
+}
+
+/// Ждёт нажатия клавиши не дольше `timeout` (без ограничения, если `None`).
+///
+/// Остальная программа печатает через `println!`, которому нужен обычный
+/// режим терминала, поэтому сырой режим включается только на время ожидания.
+fn read_input(timeout: Option<Duration>) -> io::Result<Input> {
+    terminal::enable_raw_mode()?;
+    let result = wait_for_input(timeout);
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn wait_for_input(timeout: Option<Duration>) -> io::Result<Input> {
+    if let Some(timeout) = timeout
+        && !event::poll(timeout)?
+    {
+        return Ok(Input::Timeout);
+    }
+    match event::read()? {
+        Event::Key(key) if key.kind != KeyEventKind::Release => Ok(Input::Key(key)),
+        _ => Ok(Input::Redraw),
+    }
+}
@@ -0,0 +1,233 @@
+use std::thread;
+use std::time::Duration;
+
+use super::highlight::{colors_enabled, frame_highlights, record_sorted};
+use super::graphs::ChartScale;
+use super::{GraphVisualization, Highlight, screen, terminal_size};
+use crate::sort::{Counters, SortEvent, Trace};
+
+/// Ширина панели, если массив короче: должны помещаться счётчики
+const MIN_PANE_WIDTH: usize = 28;
+/// Строки панели помимо столбцов: название, прогресс и счётчики
+const PANE_TEXT_LINES: usize = 6;
+/// Высота столбцов, даже если терминал ниже
+const MIN_BAR_ROWS: usize = 4;
+const PROGRESS_WIDTH: usize = 20;
+
+/// Одна дорожка гонки: записанная сортировка и её текущее состояние
+struct Pane {
+    label: String,
+    trace: Trace,
+    data: Vec<i32>,
+    aux: Vec<i32>,
+    marks: Vec<Highlight>,
+    sorted: Vec<bool>,
+    /// Следующее непроигранное событие трассы
+    next_event: usize,
+    ops_done: usize,
+    ops_total: usize,
+    counters: Counters,
+    phase: Option<String>,
+    /// Шаг общих часов, на котором сортировка закончилась
+    finished_at: Option<usize>,
+}
+
+impl Pane {
+    fn new(label: String, trace: Trace) -> Self {
+        let ops_total = trace.events.iter().filter(|e| is_operation(e)).count();
+        Self {
+            label,
+            data: trace.initial.clone(),
+            aux: Vec::new(),
+            marks: Vec::new(),
+            sorted: vec![false; trace.initial.len()],
+            next_event: 0,
+            ops_done: 0,
+            ops_total,
+            counters: Counters::default(),
+            phase: None,
+            finished_at: None,
+            trace,
+        }
+    }
+
+    /// Проигрывает события до следующей операции включительно; смена фазы и
+    /// готовые участки часов не тратят
+    fn advance(&mut self, clock: usize) {
+        let mut moved = false;
+        while let Some(event) = self.trace.events.get(self.next_event) {
+            // Хвост без операций проигрывается сразу, чтобы финиш совпал
+            // с последней операцией
+            if is_operation(event) && moved {
+                break;
+            }
+            self.next_event += 1;
+            event.apply(&mut self.data, &mut self.aux);
+            self.counters.record(event);
+            record_sorted(&mut self.sorted, self.data.len(), event);
+            match event {
+                SortEvent::PhaseStart(name) => self.phase = Some(name.clone()),
+                SortEvent::MarkSorted(_) => self.marks = frame_highlights(&self.sorted, None),
+                _ => {
+                    self.ops_done += 1;
+                    moved = true;
+                    self.marks = frame_highlights(&self.sorted, Some(event));
+                }
+            }
+        }
+        if self.next_event == self.trace.events.len() {
+            self.finished_at = Some(clock);
+            self.marks = vec![Highlight::Sorted; self.data.len()];
+            // Чтения и доп. память известны только записавшему трассу
+            self.counters = self.trace.counters;
+        }
+    }
+
+    fn progress_bar(&self) -> String {
+        // Трасса без операций считается пройденной целиком
+        let percent = (self.ops_done * 100).checked_div(self.ops_total).unwrap_or(100);
+        let done = percent * PROGRESS_WIDTH / 100;
+        format!("[{}{}] {:>3}%", "#".repeat(done), ".".repeat(PROGRESS_WIDTH - done), percent)
+    }
+
+    /// Строки панели: название, столбцы, прогресс и счётчики
+    fn lines(&self, columns: usize, scale: &ChartScale, place: Option<usize>) -> Vec<String> {
+        let mut lines = vec![self.label.clone()];
+        lines.extend(GraphVisualization::bar_rows(&self.data, &self.marks, columns, scale, colors_enabled()));
+        lines.push(self.progress_bar());
+        lines.push(format!("шаги: {}/{}", self.ops_done, self.ops_total));
+        lines.push(format!("cmp: {} swp: {}", self.counters.compares, self.counters.swaps));
+        lines.push(format!("wr: {} aux: {}", self.counters.writes, self.counters.aux_writes));
+        lines.push(match (self.finished_at, place) {
+            (Some(step), Some(place)) => format!("финиш #{} на шаге {}", place, step),
+            _ => self.phase.clone().unwrap_or_default(),
+        });
+        lines
+    }
+}
+
+fn is_operation(event: &SortEvent) -> bool {
+    !matches!(event, SortEvent::PhaseStart(_) | SortEvent::MarkSorted(_))
+}
+
+/// Режим гонки для `.compare ... race`: несколько алгоритмов сортируют копии
+/// одного массива рядом друг с другом, и за один шаг общих часов каждый
+/// выполняет одну операцию.
+pub struct RaceView {
+    panes: Vec<Pane>,
+    speed: f64,
+}
+
+impl RaceView {
+    /// `runs` — подписи и трассы, записанные на одном и том же массиве
+    pub fn new(runs: Vec<(String, Trace)>, speed: f64) -> Self {
+        Self {
+            panes: runs.into_iter().map(|(label, trace)| Pane::new(label, trace)).collect(),
+            speed,
+        }
+    }
+
+    /// Проигрывает гонку до финиша всех участников и возвращает подписи
+    /// в порядке финиша вместе с числом шагов
+    pub fn run(&mut self) -> Vec<(String, usize)> {
+        let mut clock = 0;
+        self.render(clock);
+        while self.panes.iter().any(|pane| pane.finished_at.is_none()) {
+            clock += 1;
+            for pane in self.panes.iter_mut().filter(|pane| pane.finished_at.is_none()) {
+                pane.advance(clock);
+            }
+            self.render(clock);
+
+            // Искусственное замедление, как при обычном проигрывании
+            if self.speed > 0.0 && self.speed != 1.0 {
+                thread::sleep(Duration::from_millis((100.0 / self.speed) as u64));
+            }
+        }
+
+        screen::release();
+
+        let mut results: Vec<(String, usize)> = self
+            .panes
+            .iter()
+            .map(|pane| (pane.label.clone(), pane.finished_at.unwrap_or(clock)))
+            .collect();
+        results.sort_by_key(|(_, steps)| *steps);
+        results
+    }
+
+    /// Место в гонке: участники, финишировавшие на одном шаге, делят место
+    fn place(&self, pane: &Pane) -> Option<usize> {
+        let step = pane.finished_at?;
+        Some(1 + self.panes.iter().filter(|p| p.finished_at.is_some_and(|s| s < step)).count())
+    }
+
+    fn render(&self, clock: usize) {
+        let mut lines = vec![format!("Гонка: шаг {}", clock)];
+
+        let (screen_width, screen_rows) = terminal_size();
+        // Панели делят ширину поровну; длинный массив сводится в колонки
+        // по ширине панели, а не растягивает её
+        let pane_width = (screen_width / self.panes.len().max(1)).saturating_sub(3).max(MIN_PANE_WIDTH);
+        let bar_columns = self
+            .panes
+            .iter()
+            .map(|pane| pane.data.len())
+            .max()
+            .unwrap_or(0)
+            .min(pane_width);
+        let per_row = (screen_width / (pane_width + 3)).max(1);
+
+        // Столбцам достаётся всё, что осталось от подписей и счётчиков
+        let pane_rows = self.panes.len().div_ceil(per_row).max(1);
+        let bar_rows = (screen_rows.saturating_sub(2) / pane_rows).saturating_sub(PANE_TEXT_LINES + 1);
+        let scale = ChartScale::new(
+            self.panes.iter().flat_map(|pane| pane.data.iter().copied()),
+            bar_rows.max(MIN_BAR_ROWS),
+        );
+
+        // Панели раскладываются в ряды, которые помещаются по ширине
+        for row in self.panes.chunks(per_row) {
+            let columns: Vec<Vec<String>> = row
+                .iter()
+                .map(|pane| pane.lines(bar_columns, &scale, self.place(pane)))
+                .collect();
+            let height = columns.iter().map(Vec::len).max().unwrap_or(0);
+            for line in 0..height {
+                let cells: Vec<String> = columns
+                    .iter()
+                    .map(|lines| fit(lines.get(line).map(String::as_str).unwrap_or(""), pane_width))
+                    .collect();
+                lines.push(cells.join(" │ ").trim_end().to_string());
+            }
+            lines.push("-".repeat(screen_width.min(row.len() * (pane_width + 3))));
+        }
+        screen::draw(&lines);
+    }
+}
+
+/// Обрезает или дополняет строку панели до ширины `width`. Цветные строки
+/// (столбцы) уже не длиннее панели, а escape-коды ширины не занимают
+fn fit(text: &str, width: usize) -> String {
+    if text.contains('\x1b') {
+        let padding = width.saturating_sub(visible_width(text));
+        return format!("{}{}", text, " ".repeat(padding));
+    }
+    let text: String = text.chars().take(width).collect();
+    format!("{:<width$}", text)
+}
+
+/// Число видимых символов без escape-последовательностей цвета
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if !in_escape => width += 1,
+            _ => {}
+        }
+    }
+    width
+}
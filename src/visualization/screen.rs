@@ -0,0 +1,245 @@
+use std::cell::RefCell;
+use std::io::{self, IsTerminal, Write};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::queue;
+use crossterm::style::Print;
+use crossterm::terminal::{Clear, ClearType};
+
+use super::cast::CastWriter;
+use super::terminal_size;
+
+/// Один символ кадра вместе с цветом, которым он выведен
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    ch: char,
+    /// Индекс escape-последовательности цвета в `Screen::styles`, 0 — без цвета
+    style: usize,
+}
+
+const BLANK: Cell = Cell { ch: ' ', style: 0 };
+
+/// Терминал, на котором идёт анимация: помнит показанный кадр и при
+/// отрисовке следующего переписывает только изменившиеся клетки.
+///
+/// Весь вывод кадра копится в буфере и уходит в терминал одной записью,
+/// а курсор на время анимации скрывается, поэтому кадры не мерцают.
+struct Screen {
+    /// Показанный кадр, по строкам; `None`, пока анимация не начата
+    previous: Option<Vec<Vec<Cell>>>,
+    /// Размер терминала, под который нарисован `previous`
+    size: (usize, usize),
+    styles: Vec<String>,
+    /// Запись кадров в файл `.cast`, если она включена
+    recording: Option<CastWriter>,
+}
+
+thread_local! {
+    static SCREEN: RefCell<Screen> = RefCell::new(Screen {
+        previous: None,
+        size: (0, 0),
+        styles: vec![String::new()],
+        recording: None,
+    });
+}
+
+/// Показывает кадр из строк, которые могут содержать цвета.
+///
+/// Если вывод идёт не в терминал, кадр печатается целиком после очистки
+/// экрана, как и раньше: перемещения курсора в файле или конвейере не нужны.
+pub fn draw(lines: &[String]) {
+    let mut out = Vec::new();
+    SCREEN.with(|screen| {
+        let mut screen = screen.borrow_mut();
+        if io::stdout().is_terminal() {
+            screen.draw(lines, &mut out);
+        } else {
+            out.extend_from_slice(b"\x1b[2J\x1b[H");
+            for line in lines {
+                out.extend_from_slice(line.as_bytes());
+                out.push(b'\n');
+            }
+        }
+        screen.record(&out);
+    });
+    write_all(&out);
+}
+
+/// Заканчивает анимацию: курсор ставится под последний кадр и снова
+/// показывается, а следующий кадр будет нарисован с чистого экрана
+pub fn release() {
+    let mut out = Vec::new();
+    SCREEN.with(|screen| {
+        let mut screen = screen.borrow_mut();
+        screen.release(&mut out);
+        screen.record(&out);
+    });
+    write_all(&out);
+}
+
+/// Начинает записывать все кадры в файл asciinema v2; начатая ранее
+/// запись при этом завершается
+pub fn start_recording(path: &str) -> io::Result<()> {
+    let (width, height) = terminal_size();
+    let writer = CastWriter::create(path, width, height)?;
+    SCREEN.with(|screen| {
+        match screen.borrow_mut().recording.replace(writer) {
+            Some(previous) => previous.finish().map(|_| ()),
+            None => Ok(()),
+        }
+    })
+}
+
+/// Завершает запись и возвращает число кадров, `None` — если записи не было
+pub fn stop_recording() -> io::Result<Option<usize>> {
+    SCREEN.with(|screen| screen.borrow_mut().recording.take().map(CastWriter::finish).transpose())
+}
+
+fn write_all(out: &[u8]) {
+    let mut stdout = io::stdout().lock();
+    // Ошибка вывода в терминал не должна прерывать сортировку
+    let _ = stdout.write_all(out).and_then(|_| stdout.flush());
+}
+
+impl Screen {
+    fn draw(&mut self, lines: &[String], out: &mut Vec<u8>) {
+        let size = terminal_size();
+        let (columns, rows) = size;
+        // Длинные строки переносятся здесь же, иначе терминал перенёс бы их
+        // сам и сбил координаты; лишнее по высоте отбрасывается
+        let mut frame: Vec<Vec<Cell>> = Vec::new();
+        for line in lines {
+            let cells = self.parse(line);
+            if cells.is_empty() {
+                frame.push(cells);
+            } else {
+                frame.extend(cells.chunks(columns.max(1)).map(<[Cell]>::to_vec));
+            }
+        }
+        frame.truncate(rows.saturating_sub(1));
+
+        let previous = match self.previous.take() {
+            Some(previous) if self.size == size => previous,
+            // Первый кадр или терминал изменил размер: рисуем с чистого экрана
+            _ => {
+                let _ = queue!(out, Hide, Clear(ClearType::All));
+                Vec::new()
+            }
+        };
+
+        let mut style = 0;
+        for y in 0..frame.len().max(previous.len()) {
+            let new = frame.get(y).map(Vec::as_slice).unwrap_or(&[]);
+            let old = previous.get(y).map(Vec::as_slice).unwrap_or(&[]);
+            let mut cursor = None;
+            for x in 0..new.len().max(old.len()) {
+                let cell = new.get(x).copied().unwrap_or(BLANK);
+                if cell == old.get(x).copied().unwrap_or(BLANK) {
+                    continue;
+                }
+                // Подряд идущие изменения пишутся без лишних перемещений
+                if cursor != Some(x) {
+                    let _ = queue!(out, MoveTo(x as u16, y as u16));
+                }
+                if cell.style != style {
+                    style = cell.style;
+                    let _ = queue!(out, Print("\x1b[0m"), Print(&self.styles[style]));
+                }
+                let _ = queue!(out, Print(cell.ch));
+                cursor = Some(x + 1);
+            }
+        }
+        if style != 0 {
+            let _ = queue!(out, Print("\x1b[0m"));
+        }
+
+        self.previous = Some(frame);
+        self.size = size;
+    }
+
+    fn record(&mut self, out: &[u8]) {
+        // Ошибка записи файла не должна прерывать сортировку: запись просто прекращается
+        if let Some(recording) = &mut self.recording
+            && recording.output(out).is_err()
+        {
+            self.recording = None;
+        }
+    }
+
+    fn release(&mut self, out: &mut Vec<u8>) {
+        if let Some(previous) = self.previous.take() {
+            let _ = queue!(out, MoveTo(0, previous.len() as u16), Show);
+        }
+    }
+
+    /// Разбирает строку на клетки, запоминая цвет каждого символа
+    fn parse(&mut self, line: &str) -> Vec<Cell> {
+        let mut cells = Vec::new();
+        let mut style = String::new();
+        let mut chars = line.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '\x1b' {
+                cells.push(Cell { ch, style: self.intern(&style) });
+                continue;
+            }
+            let mut sequence = String::from(ch);
+            for c in chars.by_ref() {
+                sequence.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            if sequence == "\x1b[0m" {
+                style.clear();
+            } else if sequence.ends_with('m') {
+                style.push_str(&sequence);
+            }
+        }
+        cells
+    }
+
+    fn intern(&mut self, style: &str) -> usize {
+        match self.styles.iter().position(|s| s == style) {
+            Some(index) => index,
+            None => {
+                self.styles.push(style.to_string());
+                self.styles.len() - 1
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen() -> Screen {
+        Screen { previous: None, size: (0, 0), styles: vec![String::new()], recording: None }
+    }
+
+    #[test]
+    fn test_redraws_only_changed_cells() {
+        let mut screen = screen();
+        let mut out = Vec::new();
+        screen.draw(&["abc".to_string(), "def".to_string()], &mut out);
+
+        let mut out = Vec::new();
+        screen.draw(&["abc".to_string(), "dxf".to_string()], &mut out);
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1b[2;2Hx");
+
+        // Исчезнувшие символы затираются пробелами
+        let mut out = Vec::new();
+        screen.draw(&["abc".to_string()], &mut out);
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1b[2;1H   ");
+    }
+
+    #[test]
+    fn test_color_changes_count_as_changes() {
+        let mut screen = screen();
+        screen.draw(&["ab".to_string()], &mut Vec::new());
+
+        let mut out = Vec::new();
+        screen.draw(&["a\x1b[31mb\x1b[0m".to_string()], &mut out);
+        assert_eq!(String::from_utf8(out).unwrap(), "\x1b[1;2H\x1b[0m\x1b[31mb\x1b[0m");
+    }
+}